@@ -0,0 +1,85 @@
+use soroban_client::xdr::{
+    Limits, ScVal, SorobanAuthorizationEntry, SorobanAuthorizedInvocation, SorobanCredentials,
+    WriteXdr,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CredentialKind {
+    SourceAccount,
+    Address,
+}
+
+impl CredentialKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CredentialKind::SourceAccount => "source_account",
+            CredentialKind::Address => "address",
+        }
+    }
+}
+
+// one decoded SorobanAuthorizationEntry
+#[derive(Debug, Clone)]
+pub struct AuthEntryInfo {
+    pub credential: CredentialKind,
+    // authorizing address, None for source account credentials
+    pub address: Option<String>,
+    pub signatures: usize,
+    // depth of the authorized invocation tree, 1 for a single root call
+    pub depth: usize,
+    // number of authorized invocations in the tree
+    pub invocations: usize,
+    pub bytes: usize,
+}
+
+pub fn auth_entries(entries: &[SorobanAuthorizationEntry], limits: Limits) -> Vec<AuthEntryInfo> {
+    entries
+        .iter()
+        .map(|entry| {
+            let (credential, address, signatures) = match &entry.credentials {
+                SorobanCredentials::SourceAccount => (CredentialKind::SourceAccount, None, 0),
+                SorobanCredentials::Address(creds) => (
+                    CredentialKind::Address,
+                    Some(creds.address.to_string()),
+                    signature_count(&creds.signature),
+                ),
+            };
+            AuthEntryInfo {
+                credential,
+                address,
+                signatures,
+                depth: invocation_depth(&entry.root_invocation),
+                invocations: invocation_count(&entry.root_invocation),
+                bytes: entry.to_xdr(limits.clone()).map(|b| b.len()).unwrap_or(0),
+            }
+        })
+        .collect()
+}
+
+// account signatures are a vec of {public_key, signature} maps,
+// custom accounts may use any value
+fn signature_count(signature: &ScVal) -> usize {
+    match signature {
+        ScVal::Void => 0,
+        ScVal::Vec(Some(sigs)) => sigs.len(),
+        ScVal::Vec(None) => 0,
+        _ => 1,
+    }
+}
+
+fn invocation_depth(invocation: &SorobanAuthorizedInvocation) -> usize {
+    1 + invocation
+        .sub_invocations
+        .iter()
+        .map(invocation_depth)
+        .max()
+        .unwrap_or(0)
+}
+
+fn invocation_count(invocation: &SorobanAuthorizedInvocation) -> usize {
+    1 + invocation
+        .sub_invocations
+        .iter()
+        .map(invocation_count)
+        .sum::<usize>()
+}
@@ -0,0 +1,315 @@
+use soroban_client::xdr::{ConfigSettingEntry, ConfigSettingId};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::fee;
+use crate::headroom;
+use crate::rpc_server::ContractStore;
+use crate::show::{self, FuncStatistics};
+
+// config settings of the ledger limits plus tx count, parallelism and close time
+pub const CAPACITY_SETTINGS: [ConfigSettingId; 6] = [
+    ConfigSettingId::ContractComputeV0,
+    ConfigSettingId::ContractLedgerCostV0,
+    ConfigSettingId::ContractBandwidthV0,
+    ConfigSettingId::ContractExecutionLanes,
+    ConfigSettingId::ContractParallelComputeV0,
+    ConfigSettingId::ScpTiming,
+];
+
+// each call is a transaction, limited by the ledger max tx count
+pub const TX_COUNT: &str = "tx_count";
+
+#[derive(Debug, Clone)]
+pub struct LedgerCapacity {
+    // ledger-wide limits keyed like the metric limits
+    pub limits: HashMap<&'static str, u64>,
+    pub max_tx_count: Option<u64>,
+    // independent clusters applied in parallel, each with the full
+    // instruction limit
+    pub clusters: u64,
+    pub close_time: Duration,
+}
+
+impl Default for LedgerCapacity {
+    fn default() -> Self {
+        Self {
+            limits: show::stellar_ledger_limits_config(),
+            max_tx_count: Some(1_000),
+            clusters: 1,
+            close_time: Duration::from_secs(5),
+        }
+    }
+}
+
+impl LedgerCapacity {
+    // None if a ledger limit setting is missing, the others fall back to
+    // the defaults
+    pub fn from_settings(settings: &[ConfigSettingEntry]) -> Option<Self> {
+        let mut res = LedgerCapacity {
+            limits: headroom::ledger_limits_from_settings(settings)?,
+            ..Default::default()
+        };
+        for setting in settings {
+            match setting {
+                ConfigSettingEntry::ContractExecutionLanes(v) => {
+                    res.max_tx_count = Some(v.ledger_max_tx_count as u64)
+                }
+                ConfigSettingEntry::ContractParallelComputeV0(v) => {
+                    res.clusters = (v.ledger_max_dependent_tx_clusters as u64).max(1)
+                }
+                ConfigSettingEntry::ScpTiming(v) => {
+                    res.close_time =
+                        Duration::from_millis(v.ledger_target_close_time_milliseconds as u64)
+                }
+                _ => {}
+            }
+        }
+        Some(res)
+    }
+
+    fn limit(&self, key: &str) -> Option<f64> {
+        let limit = *self.limits.get(key)? as f64;
+        if key == "cpu_insns" {
+            return Some(limit * self.clusters as f64);
+        }
+        Some(limit)
+    }
+
+    // calls each using `avgs` fitting in a ledger, and the limit reached
+    // first
+    pub(crate) fn fit(&self, avgs: &HashMap<&'static str, f64>) -> Option<(f64, &'static str)> {
+        let by_metric = avgs
+            .iter()
+            .filter(|(_, avg)| **avg > 0.0)
+            .filter_map(|(key, avg)| Some((self.limit(key)? / avg, *key)));
+        let by_count = self.max_tx_count.map(|n| (n as f64, TX_COUNT));
+        by_metric.chain(by_count).min_by(|a, b| a.0.total_cmp(&b.0))
+    }
+
+    pub(crate) fn per_second(&self, per_ledger: f64) -> f64 {
+        per_ledger / self.close_time.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+// disk read entries of each sample of `function`, the ledger limits these
+// and not every read-only key
+pub(crate) fn disk_reads<'a>(
+    store: &'a ContractStore,
+    contract: &str,
+    function: &str,
+) -> impl Iterator<Item = usize> + 'a {
+    store
+        .get(contract)
+        .and_then(|funcs| funcs.get(function))
+        .into_iter()
+        .flatten()
+        .map(|s| fee::disk_read_entries(&s.footprint))
+}
+
+// average usage of one call, with entry_reads counting disk reads only
+fn average_usage(
+    store: &ContractStore,
+    contract: &str,
+    function: &str,
+    stats: &FuncStatistics,
+) -> HashMap<&'static str, f64> {
+    let mut avgs: HashMap<&'static str, f64> =
+        stats.metrics.iter().map(|(k, s)| (*k, s.avg)).collect();
+    let reads: Vec<usize> = disk_reads(store, contract, function).collect();
+    if !reads.is_empty() {
+        avgs.insert(
+            "entry_reads",
+            reads.iter().sum::<usize>() as f64 / reads.len() as f64,
+        );
+    }
+    avgs
+}
+
+#[derive(Debug, Clone)]
+pub struct FunctionCapacity {
+    pub contract: String,
+    pub function: String,
+    pub per_ledger: f64,
+    pub per_second: f64,
+    pub binding: &'static str,
+}
+
+#[derive(Debug, Clone)]
+pub struct WorkloadCapacity {
+    // (contract, function, share of the calls, calls per ledger)
+    pub calls: Vec<(String, String, f64, f64)>,
+    pub per_ledger: f64,
+    pub per_second: f64,
+    pub binding: &'static str,
+}
+
+// throughput of each function called alone, from its average usage
+pub fn capacity(store: &ContractStore, ledger: &LedgerCapacity) -> Vec<FunctionCapacity> {
+    let mut res = vec![];
+    for (contract, funcs) in show::calc_statistics(store) {
+        for (function, stats) in funcs {
+            let avgs = average_usage(store, &contract, &function, &stats);
+            let Some((per_ledger, binding)) = ledger.fit(&avgs) else {
+                continue;
+            };
+            res.push(FunctionCapacity {
+                contract: contract.clone(),
+                function,
+                per_ledger,
+                per_second: ledger.per_second(per_ledger),
+                binding,
+            });
+        }
+    }
+    res.sort_by(|a, b| (&a.contract, &a.function).cmp(&(&b.contract, &b.function)));
+    res
+}
+
+// throughput of calls spread over `mix` (contract, function, weight),
+// None if a function was not collected
+pub fn workload_capacity(
+    store: &ContractStore,
+    ledger: &LedgerCapacity,
+    mix: &[(String, String, f64)],
+) -> Option<WorkloadCapacity> {
+    let statistics = show::calc_statistics(store);
+    let total_weight: f64 = mix.iter().map(|(_, _, w)| w).sum();
+    if total_weight <= 0.0 {
+        return None;
+    }
+
+    // average usage of one call of the mix
+    let mut avgs: HashMap<&'static str, f64> = HashMap::new();
+    for (contract, function, weight) in mix {
+        let Some(stats) = statistics.get(contract).and_then(|f| f.get(function)) else {
+            warn!(%contract, %function, "workload function was not collected");
+            return None;
+        };
+        for (key, avg) in average_usage(store, contract, function, stats) {
+            *avgs.entry(key).or_default() += avg * weight / total_weight;
+        }
+    }
+    let (per_ledger, binding) = ledger.fit(&avgs)?;
+    Some(WorkloadCapacity {
+        calls: mix
+            .iter()
+            .map(|(contract, function, weight)| {
+                let share = weight / total_weight;
+                (
+                    contract.clone(),
+                    function.clone(),
+                    share,
+                    per_ledger * share,
+                )
+            })
+            .collect(),
+        per_ledger,
+        per_second: ledger.per_second(per_ledger),
+        binding,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::footprint::test::entry;
+    use crate::footprint::{EntryChange, EntryKind};
+    use crate::statistics::ResourceMetric;
+
+    fn sample(cpu_insns: u64, write_bytes: u32) -> ResourceMetric {
+        ResourceMetric {
+            cpu_insns: Some(cpu_insns),
+            write_bytes: Some(write_bytes),
+            ..Default::default()
+        }
+    }
+
+    fn ledger() -> LedgerCapacity {
+        LedgerCapacity {
+            limits: HashMap::from([("cpu_insns", 100_000_000), ("write_bytes", 100_000)]),
+            max_tx_count: Some(1_000),
+            clusters: 2,
+            close_time: Duration::from_secs(5),
+        }
+    }
+
+    #[test]
+    fn test_fit() {
+        let ledger = ledger();
+        // 2 clusters of 100M instructions
+        let avgs = HashMap::from([("cpu_insns", 10_000_000.0), ("write_bytes", 1_000.0)]);
+        assert_eq!(ledger.fit(&avgs), Some((20.0, "cpu_insns")));
+        let avgs = HashMap::from([("cpu_insns", 1_000_000.0), ("write_bytes", 2_000.0)]);
+        assert_eq!(ledger.fit(&avgs), Some((50.0, "write_bytes")));
+        // unused and unlimited metrics leave the tx count
+        let avgs = HashMap::from([("cpu_insns", 0.0), ("mem_bytes", 1e9)]);
+        assert_eq!(ledger.fit(&avgs), Some((1_000.0, TX_COUNT)));
+        let ledger = LedgerCapacity {
+            max_tx_count: None,
+            ..ledger
+        };
+        assert_eq!(ledger.fit(&avgs), None);
+        assert_eq!(ledger.per_second(50.0), 10.0);
+    }
+
+    #[test]
+    fn test_workload_capacity() {
+        let store = ContractStore::from([(
+            "C1".to_string(),
+            HashMap::from([
+                ("mint".to_string(), vec![sample(20_000_000, 0)]),
+                ("transfer".to_string(), vec![sample(0, 4_000)]),
+            ]),
+        )]);
+        let mix = [
+            ("C1".to_string(), "mint".to_string(), 1.0),
+            ("C1".to_string(), "transfer".to_string(), 3.0),
+        ];
+        let w = workload_capacity(&store, &ledger(), &mix).unwrap();
+        // an average call uses 5M instructions and 3000 write bytes
+        assert_eq!(w.binding, "write_bytes");
+        assert!((w.per_ledger - 100_000.0 / 3_000.0).abs() < 1e-9);
+        assert!((w.per_second - w.per_ledger / 5.0).abs() < 1e-9);
+        assert_eq!(w.calls.len(), 2);
+        assert_eq!(w.calls[0].2, 0.25);
+        assert!((w.calls[1].3 - w.per_ledger * 0.75).abs() < 1e-9);
+
+        let missing = [("C1".to_string(), "burn".to_string(), 1.0)];
+        assert!(workload_capacity(&store, &ledger(), &missing).is_none());
+        let empty = [("C1".to_string(), "mint".to_string(), 0.0)];
+        assert!(workload_capacity(&store, &ledger(), &empty).is_none());
+    }
+
+    #[test]
+    fn test_disk_reads() {
+        // live contract entries are read-only keys but not disk reads
+        let sample = ResourceMetric {
+            cpu_insns: Some(10_000_000),
+            entry_reads: Some(3),
+            footprint: vec![
+                entry(EntryKind::ContractInstance, EntryChange::Unchanged),
+                entry(EntryKind::ContractCode, EntryChange::Unchanged),
+                entry(EntryKind::PersistentData, EntryChange::Unchanged),
+            ],
+            ..Default::default()
+        };
+        let store = ContractStore::from([(
+            "C1".to_string(),
+            HashMap::from([("transfer".to_string(), vec![sample])]),
+        )]);
+        let mut ledger = ledger();
+        ledger.limits.insert("entry_reads", 2);
+
+        let res = capacity(&store, &ledger);
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].binding, "cpu_insns");
+        assert_eq!(res[0].per_ledger, 20.0);
+
+        let mix = [("C1".to_string(), "transfer".to_string(), 1.0)];
+        let w = workload_capacity(&store, &ledger, &mix).unwrap();
+        assert_eq!(w.binding, "cpu_insns");
+        assert_eq!(w.per_ledger, 20.0);
+    }
+}
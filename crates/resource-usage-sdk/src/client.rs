@@ -1,252 +1,330 @@
-use soroban_client::error::Error;
 use soroban_client::{
-    account::{Account, AccountBehavior},
-    address::{Address, AddressTrait},
+    account::Account,
     contract::{ContractBehavior, Contracts},
     keypair::{Keypair, KeypairBehavior},
-    network::{NetworkPassphrase, Networks},
     operation::{self, Operation},
-    soroban_rpc::TransactionStatus,
-    transaction::{TransactionBehavior, TransactionBuilder, TransactionBuilderBehavior},
-    xdr, Options, Server,
+    soroban_rpc::{GetTransactionResponse, TransactionStatus},
+    transaction::{self, Transaction, TransactionBuilder, TransactionBuilderBehavior},
+    xdr::{self, InvokeHostFunctionOp, OperationBody, ScVal, SorobanCredentials},
+    Options,
 };
-use std::time::Duration;
 
-struct Client {
+use tracing::{debug, field::Empty, info, instrument, Span};
+
+use crate::{Error, NetworkConfig, SignerRegistry, StellarRpcServer};
+
+// name of the funded source account in the signer registry
+pub const SOURCE_ACCOUNT: &str = "source";
+// ledgers during which the signed auth entries stay valid
+const AUTH_VALID_LEDGERS: u32 = 100;
+
+// test harness, every soroban call made through it goes through
+// StellarRpcServer and shows up in print_table
+pub struct Client {
+    server: StellarRpcServer,
     source_keypair: Keypair,
-    source_account: Account,
-    server: Server,
+    signers: SignerRegistry,
 }
 
 impl Client {
-    async fn new(url: &str) -> Result<Client, Error> {
-        let mut options = Options::default();
-        options.allow_http = true;
-        let server = Server::new(url, options).expect("Cannot create server");
-        let source_keypair = Keypair::random().unwrap();
-        let source_public_key = &source_keypair.public_key();
-        // Get account information from server
-        let account_data = server.request_airdrop(source_public_key).await?;
-        let source_account =
-            Account::new(source_public_key, &account_data.sequence_number()).unwrap();
-        return Ok(Client {
-            source_keypair,
-            source_account,
+    // standalone network with default fees and timeouts
+    pub async fn new(url: &str, opts: Options) -> Result<Client, Error> {
+        Client::with_config(url, opts, NetworkConfig::default()).await
+    }
+
+    // random source account funded by friendbot
+    pub async fn with_config(
+        url: &str,
+        opts: Options,
+        config: NetworkConfig,
+    ) -> Result<Client, Error> {
+        Client::with_keypair(url, opts, config, random_keypair()?).await
+    }
+
+    pub async fn with_keypair(
+        url: &str,
+        opts: Options,
+        config: NetworkConfig,
+        source_keypair: Keypair,
+    ) -> Result<Client, Error> {
+        let server = StellarRpcServer::with_config(url, opts, config).await?;
+        server.request_airdrop(&source_keypair.public_key()).await?;
+        let mut signers = SignerRegistry::new();
+        signers.add_keypair(SOURCE_ACCOUNT, source_keypair.clone());
+        Ok(Client {
             server,
-        });
+            source_keypair,
+            signers,
+        })
     }
 
-    async fn deploy_contact(&mut self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let wasm;
-        match std::fs::read(path) {
-            Ok(read_data) => {
-                wasm = read_data;
-            }
-            Err(_error) => return Err("file path error".into()),
-        };
-        //
-        // Uploading the WASM executable
-        //
-        let upload = Operation::new()
-            .upload_wasm(&wasm, None)
-            .expect("Cannot create upload_wasm operation");
-        let tx = TransactionBuilder::new(&mut self.source_account, Networks::standalone(), None)
-            .fee(1000u32)
-            .add_operation(upload)
-            .build();
-        let signers = [self.source_keypair.clone()];
-        let mut ptx = self.server.prepare_transaction(&tx).await?;
-        ptx.sign(&signers);
-
-        println!("> Uploading WASM executable");
-        let response = self.server.send_transaction(ptx).await?;
+    pub fn server(&self) -> &StellarRpcServer {
+        &self.server
+    }
 
-        let hash = response.hash;
-        println!(">> Tx hash: {hash}");
-        let wasm_hash = match self
-            .server
-            .wait_transaction(&hash, Duration::from_secs(15))
-            .await
-        {
-            Ok(tx_result) if tx_result.status == TransactionStatus::Success => {
-                let (_meta, ret_val) = tx_result.to_result_meta().expect("No meta found");
-                if let Some(scval) = ret_val {
-                    let bytes: Vec<u8> = scval.try_into().expect("Cannot convert ScVal to Vec<u8>");
-                    *bytes.last_chunk::<32>().expect("Not 32 bytes")
-                } else {
-                    return Err(">> None return value".into());
-                }
-            }
-            _ => {
-                println!(">> Failed to upload the WASM executable");
-                return Err(">> Failed to upload the wasm".into());
-            }
-        };
-        println!(">> Wasm hash: {}", hex::encode(wasm_hash));
-        println!();
+    pub fn server_mut(&mut self) -> &mut StellarRpcServer {
+        &mut self.server
+    }
+
+    pub fn keypair(&self) -> &Keypair {
+        &self.source_keypair
+    }
+
+    pub fn public_key(&self) -> String {
+        self.source_keypair.public_key()
+    }
+
+    pub fn signers(&self) -> &SignerRegistry {
+        &self.signers
+    }
+
+    // register extra accounts, multisig signers or contract accounts here,
+    // their auth entries are signed automatically
+    pub fn signers_mut(&mut self) -> &mut SignerRegistry {
+        &mut self.signers
+    }
+
+    // random account funded by friendbot and registered as `name`,
+    // returns its address
+    pub async fn add_account(&mut self, name: &str) -> Result<String, Error> {
+        let keypair = random_keypair()?;
+        let address = keypair.public_key();
+        self.server.request_airdrop(&address).await?;
+        self.signers.add_keypair(name, keypair);
+        info!(name, %address, "account created");
+        Ok(address)
+    }
+
+    // restore archived entries with the source account before invoking,
+    // the restores show up as "<function>:restore" in print_table
+    pub fn enable_auto_restore(&mut self) {
+        self.server.set_auto_restore(self.source_keypair.clone());
+    }
+
+    pub async fn airdrop(&self, public_key: &str) -> Result<Account, Error> {
+        Ok(self.server.request_airdrop(public_key).await?)
+    }
+
+    // create `public_key` with `starting_balance` stroops from the source account
+    pub async fn fund_account(
+        &mut self,
+        public_key: &str,
+        starting_balance: i64,
+    ) -> Result<(), Error> {
+        let create_account = Operation::new()
+            .create_account(public_key, starting_balance)
+            .map_err(operation_error)?;
+        let mut account = self.load_account(SOURCE_ACCOUNT).await?;
+        let mut tx = self.build_transaction(&mut account, create_account);
+        self.signers.sign_transaction(&mut tx, SOURCE_ACCOUNT)?;
+        let response = self.server.send_transaction(tx).await?;
+        self.wait(&response.hash).await?;
+        Ok(())
+    }
+
+    pub async fn create_account(&mut self, public_key: &str) -> Result<(), Error> {
+        self.fund_account(public_key, operation::ONE).await
+    }
+
+    // returns the wasm hash
+    pub async fn upload_wasm(&mut self, wasm: &[u8]) -> Result<[u8; 32], Error> {
+        let upload = Operation::new()
+            .upload_wasm(wasm, None)
+            .map_err(operation_error)?;
+        let ret_val = self.submit(SOURCE_ACCOUNT, upload).await?;
+        match ret_val {
+            Some(ScVal::Bytes(bytes)) => bytes
+                .as_slice()
+                .try_into()
+                .map_err(|_| Error::MissingReturnValue),
+            _ => Err(Error::MissingReturnValue),
+        }
+    }
 
-        //
-        // Create the contract for the uploaded WASM
-        //
+    // returns the contract id
+    pub async fn deploy(
+        &mut self,
+        wasm_hash: [u8; 32],
+        constructor_args: Vec<ScVal>,
+    ) -> Result<String, Error> {
         let create_contract = Operation::new()
             .create_contract(
                 &self.source_keypair.public_key(),
                 wasm_hash,
                 None,
                 None,
-                [].into(),
+                constructor_args,
             )
-            .expect("Cannot create create_contract operation");
-        let tx = TransactionBuilder::new(&mut self.source_account, Networks::standalone(), None)
-            .fee(1000u32)
-            .add_operation(create_contract)
-            .build();
-
-        let mut ptx = self.server.prepare_transaction(&tx).await?;
-        ptx.sign(&signers);
-
-        println!(
-            "> Creating the contract for WASM hash {}",
-            hex::encode(wasm_hash)
-        );
-        let response = self.server.send_transaction(ptx).await?;
-
-        let hash = response.hash;
-        println!(">> Tx hash: {hash}");
-        let contract_addr = match self
-            .server
-            .wait_transaction(&hash, Duration::from_secs(15))
-            .await
-        {
-            Ok(tx_result) if tx_result.status == TransactionStatus::Success => {
-                let (_meta, ret_val) = tx_result.to_result_meta().expect("No meta");
-                if let Some(xdr::ScVal::Address(addr)) = ret_val {
-                    Address::from_sc_address(&addr).unwrap()
-                } else {
-                    return Err("Failed to create contract".into());
-                }
+            .map_err(operation_error)?;
+        match self.submit(SOURCE_ACCOUNT, create_contract).await? {
+            Some(ScVal::Address(addr)) => {
+                info!(contract = %addr, "contract deployed");
+                Ok(addr.to_string())
             }
-            _ => return Err("Failed to create contract".into()),
-        };
-        println!(">> Contract id: {}", contract_addr.to_string());
-        println!();
-        Ok(())
+            _ => Err(Error::MissingReturnValue),
+        }
     }
 
-    async fn airdrop(&self, public_key: &String) -> Result<Account, Error> {
-        self.server.request_airdrop(public_key).await
+    // upload and deploy the wasm file at `path`, returns the contract id
+    pub async fn deploy_wasm(
+        &mut self,
+        path: &str,
+        constructor_args: Vec<ScVal>,
+    ) -> Result<String, Error> {
+        let wasm = std::fs::read(path)?;
+        let wasm_hash = self.upload_wasm(&wasm).await?;
+        self.deploy(wasm_hash, constructor_args).await
     }
 
-    pub async fn create_account(
+    // returns the value returned by the contract function
+    pub async fn invoke(
         &mut self,
-        public_key: &String,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        // Get account information from server
-        let create_account_op = Operation::new()
-            .create_account(public_key, operation::ONE)
-            .expect("Cannot create operation");
-
-        let mut builder =
-            TransactionBuilder::new(&mut self.source_account, Networks::standalone(), None);
-        builder.fee(1000u32);
-        builder.add_operation(create_account_op);
-
-        let mut tx = builder.build();
-        let source_keypair = self.source_keypair.clone();
-        tx.sign(&[source_keypair]);
-        let response = self.server.send_transaction(tx).await?;
-        println!("response:{:?}", response);
-        println!("response error result:{:?}", response.to_error_result());
-
-        let hash = response.hash;
-
-        println!(">> Tx hash: {hash}");
-        match self
-            .server
-            .wait_transaction(&hash, Duration::from_secs(15))
+        contract_id: &str,
+        function: &str,
+        args: Vec<ScVal>,
+    ) -> Result<Option<ScVal>, Error> {
+        self.invoke_as(SOURCE_ACCOUNT, contract_id, function, args)
             .await
-        {
-            Ok(tx_result) => match tx_result.status {
-                TransactionStatus::Success => {
-                    println!("Transaction successful!");
-                    println!();
-                    if let Some(ledger) = tx_result.ledger {
-                        println!("Confirmed in ledger: {}", ledger);
-                    }
-                    Ok(())
-                }
-                TransactionStatus::Failed => {
-                    if let Some(result) = tx_result.to_result() {
-                        eprintln!("Transaction failed with result: {:?}", result);
-                    } else {
-                        eprintln!("Transaction failed without result XDR");
-                    }
-                    Ok(())
-                }
-                TransactionStatus::NotFound => {
-                    eprintln!("Transaction not found");
-                    Err("Transaction not found".into())
-                }
-            },
-            Err(e) => Err(e.0.to_string().into()),
-        }
     }
 
-    async fn invoke(
+    // invoke with the registered account `source` as transaction source
+    pub async fn invoke_as(
         &mut self,
-        contract_addr: Address,
+        source: &str,
+        contract_id: &str,
         function: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let signers = [self.source_keypair.clone()];
-        let contract = Contracts::new(&contract_addr.to_string()).unwrap();
-
-        let tx = TransactionBuilder::new(&mut self.source_account, Networks::standalone(), None)
-            .fee(1000u32)
-            .add_operation(contract.call(
-                "increment",
-                Some(vec![
-                    Address::account(signers[0].raw_public_key())?.to_sc_val()?,
-                    3u32.into(),
-                ]),
-            ))
-            .build();
-
-        let mut ptx = self.server.prepare_transaction(&tx).await?;
-        ptx.sign(&signers);
-
-        println!(
-            "> Calling increment on contract {}",
-            contract_addr.to_string()
-        );
-        let response = self.server.send_transaction(ptx).await?;
+        args: Vec<ScVal>,
+    ) -> Result<Option<ScVal>, Error> {
+        let contract =
+            Contracts::new(contract_id).map_err(|e| Error::InvalidArgument(e.to_string()))?;
+        self.submit(source, contract.call(function, Some(args)))
+            .await
+    }
 
-        let hash = response.hash;
-        println!(">> Tx hash: {hash}");
-        match self
+    pub async fn print_table(&mut self) -> Result<(), Error> {
+        self.server.print_table().await
+    }
+
+    // simulate, sign and send a single soroban operation. address auth
+    // entries of registered accounts are signed and the transaction is
+    // simulated again so the measured resources include the signatures
+    #[instrument(skip(self, op), fields(tx_hash = Empty))]
+    async fn submit(&mut self, source: &str, op: xdr::Operation) -> Result<Option<ScVal>, Error> {
+        let mut account = self.load_account(source).await?;
+        let mut tx = self.build_transaction(&mut account.clone(), op.clone());
+        let mut sim = self
             .server
-            .wait_transaction(&hash, Duration::from_secs(15))
-            .await
-        {
-            Ok(tx_result) if tx_result.status == TransactionStatus::Success => {
-                let (_meta, ret_val) = tx_result.to_result_meta().expect("No result meta");
-                ret_val
-                    .expect("None returned value")
-                    .try_into()
-                    .expect("Return value is not u32")
+            .simulate_transaction(&tx, self.server.config().simulation_options())
+            .await?;
+        if self.server.restore_footprint(&tx, &sim).await? {
+            // the restore may have used the sequence number of `source`
+            account = self.load_account(source).await?;
+            tx = self.build_transaction(&mut account.clone(), op.clone());
+            sim = self
+                .server
+                .simulate_transaction(&tx, self.server.config().simulation_options())
+                .await?;
+        }
+
+        let auth = sim.to_result().map(|(_, auth)| auth).unwrap_or_default();
+        let needs_signing = auth
+            .iter()
+            .any(|entry| matches!(entry.credentials, SorobanCredentials::Address(_)));
+        let mut ptx = match (&op.body, needs_signing) {
+            (
+                OperationBody::InvokeHostFunction(InvokeHostFunctionOp { host_function, .. }),
+                true,
+            ) => {
+                debug!(entries = auth.len(), "signing auth entries");
+                let signed = self.signers.sign_auth_entries(
+                    &auth,
+                    self.server.config().network.passphrase(),
+                    sim.latest_ledger + AUTH_VALID_LEDGERS,
+                )?;
+                let op = Operation::new()
+                    .invoke_host_function(host_function.clone(), Some(signed))
+                    .map_err(operation_error)?;
+                let tx = self.build_transaction(&mut account.clone(), op);
+                self.server.prepare_transaction(&tx).await?
             }
-            _ => return Err("Failed to create contract".into()),
+            _ => transaction::assemble_transaction(&tx, sim)?,
         };
-        Ok(())
+        self.signers.sign_transaction(&mut ptx, source)?;
+        let response = self.server.send_transaction(ptx).await?;
+        Span::current().record("tx_hash", response.hash.as_str());
+        let tx_result = self.wait(&response.hash).await?;
+        let (_meta, ret_val) = tx_result.to_result_meta().ok_or(Error::MissingMeta)?;
+        Ok(ret_val)
+    }
+
+    async fn load_account(&self, name: &str) -> Result<Account, Error> {
+        let address = self
+            .signers
+            .address(name)
+            .ok_or_else(|| Error::UnknownSigner(name.to_string()))?;
+        Ok(self.server.get_account(address).await?)
+    }
+
+    fn build_transaction(&self, account: &mut Account, op: xdr::Operation) -> Transaction {
+        TransactionBuilder::new(account, self.server.config().network.passphrase(), None)
+            .fee(self.server.config().base_fee)
+            .add_operation(op)
+            .build()
+    }
+
+    async fn wait(&self, hash: &str) -> Result<GetTransactionResponse, Error> {
+        let tx_result = self
+            .server
+            .wait_transaction(hash, self.server.config().wait_time)
+            .await
+            .map_err(|(e, _)| e)?;
+        if tx_result.status != TransactionStatus::Success {
+            return Err(Error::TransactionFailed(hash.to_string()));
+        }
+        Ok(tx_result)
     }
 }
 
-#[tokio::test]
-async fn test() {
-    let mut client = Client::new("http://localhost:8000/rpc").await.unwrap();
-    let keypair = Keypair::random().unwrap();
-    client.create_account(&keypair.public_key()).await.unwrap();
-    // client.airdrop(&"".to_string());
-    // client.deploy_contact("");
-    // let address = Address::new("").unwrap();
-    // client.invoke(address, "increment", []);
+fn random_keypair() -> Result<Keypair, Error> {
+    Keypair::random().map_err(|e| Error::InvalidArgument(e.to_string()))
+}
+
+fn operation_error(e: operation::Error) -> Error {
+    Error::InvalidArgument(format!("{e:?}"))
+}
+
+#[test]
+fn test() {
+    let mock = crate::MockRpc::start().unwrap();
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    rt.block_on(async {
+        let config = NetworkConfig {
+            poll_interval: std::time::Duration::from_millis(10),
+            ..Default::default()
+        };
+        let mut client = Client::with_config(&mock.url(), crate::MockRpc::options(), config)
+            .await
+            .unwrap();
+        let keypair = Keypair::random().unwrap();
+        client.create_account(&keypair.public_key()).await.unwrap();
+        // loadable once the create account operation is applied
+        client
+            .server()
+            .get_account(&keypair.public_key())
+            .await
+            .unwrap();
+
+        let sent = mock.sent();
+        assert_eq!(sent.len(), 1);
+        let xdr::TransactionEnvelope::Tx(create) = &sent[0] else {
+            panic!("unexpected envelope");
+        };
+        assert!(matches!(
+            create.tx.operations[0].body,
+            OperationBody::CreateAccount(_)
+        ));
+        assert_eq!(create.tx.source_account.to_string(), client.public_key());
+        let requests = mock.requests();
+        assert_eq!(requests.iter().filter(|m| *m == "friendbot").count(), 1);
+    });
 }
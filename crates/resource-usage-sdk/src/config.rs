@@ -0,0 +1,84 @@
+use soroban_client::network::{NetworkPassphrase, Networks};
+use soroban_client::SimulationOptions;
+use std::time::Duration;
+
+pub const DEFAULT_BASE_FEE: u32 = 1000;
+pub const DEFAULT_WAIT_TIME: Duration = Duration::from_secs(10);
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Network {
+    Standalone,
+    Testnet,
+    Futurenet,
+    Mainnet,
+    Custom(String),
+}
+
+impl Network {
+    pub fn passphrase(&self) -> &str {
+        match self {
+            Network::Standalone => Networks::standalone(),
+            Network::Testnet => Networks::testnet(),
+            Network::Futurenet => Networks::futurenet(),
+            Network::Mainnet => Networks::public(),
+            Network::Custom(passphrase) => passphrase,
+        }
+    }
+
+    // short name, e.g. for metric labels
+    pub fn name(&self) -> &str {
+        match self {
+            Network::Standalone => "standalone",
+            Network::Testnet => "testnet",
+            Network::Futurenet => "futurenet",
+            Network::Mainnet => "mainnet",
+            Network::Custom(_) => "custom",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NetworkConfig {
+    pub network: Network,
+    // inclusion fee of every transaction, in stroops
+    pub base_fee: u32,
+    // extra cpu instructions budgeted on top of the simulated ones,
+    // raises the resource fee accordingly
+    pub resource_leeway: u64,
+    // how long to wait for a sent transaction
+    pub wait_time: Duration,
+    // delay between two get_transaction polls
+    pub poll_interval: Duration,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            network: Network::Standalone,
+            base_fee: DEFAULT_BASE_FEE,
+            resource_leeway: 0,
+            wait_time: DEFAULT_WAIT_TIME,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+}
+
+impl NetworkConfig {
+    pub fn new(network: Network) -> Self {
+        Self {
+            network,
+            ..Default::default()
+        }
+    }
+
+    pub fn simulation_options(&self) -> Option<SimulationOptions> {
+        if self.resource_leeway == 0 {
+            return None;
+        }
+        Some(SimulationOptions {
+            cpu_instructions: self.resource_leeway,
+            auth_mode: None,
+        })
+    }
+}
@@ -9,6 +9,27 @@ pub enum Error {
     #[error("sdk xdr error:{0:?}")]
     SDKXdrError(#[from] soroban_client::xdr::Error),
 
+    #[error("network passphrase mismatch, expected {expected:?} got {actual:?}")]
+    NetworkMismatch { expected: String, actual: String },
+
+    #[error("transaction not found")]
+    TransactionNotFound,
+
+    #[error("transaction failed:{0}")]
+    TransactionFailed(String),
+
+    #[error("invalid argument:{0}")]
+    InvalidArgument(String),
+
+    #[error("unknown signer:{0}")]
+    UnknownSigner(String),
+
+    #[error("signer error:{0}")]
+    SignerError(String),
+
+    #[error("missing return value")]
+    MissingReturnValue,
+
     #[error("missing transaction meta")]
     MissingMeta,
 
@@ -17,4 +38,36 @@ pub enum Error {
 
     #[error("simulate no transaction data")]
     NoTransactionData,
+
+    #[error("missing network config setting")]
+    MissingConfigSetting,
+
+    #[error("contract wasm not found")]
+    MissingContractWasm,
+
+    #[error("scenario error:{0}")]
+    ScenarioError(String),
+
+    #[error("toml error:{0}")]
+    TomlError(#[from] toml::de::Error),
+
+    #[error("json error:{0}")]
+    JsonError(#[from] serde_json::Error),
+
+    #[cfg(feature = "sqlite")]
+    #[error("sqlite error:{0}")]
+    SqliteError(#[from] rusqlite::Error),
+
+    #[cfg(feature = "sqlite")]
+    #[error("database schema version {0} is newer than supported")]
+    UnsupportedSchema(i64),
+
+    #[error("io error:{0:?}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("wasm parse error:{0:?}")]
+    WasmParseError(#[from] wasmparser::BinaryReaderError),
+
+    #[error("archived entries must be restored first, see set_auto_restore")]
+    RestoreRequired,
 }
@@ -0,0 +1,309 @@
+use soroban_client::xdr::{ConfigSettingEntry, ConfigSettingId};
+
+use crate::footprint::{EntryChange, EntryKind, FootprintEntry};
+use crate::rent::{div_ceil, DATA_SIZE_1KB_INCREMENT};
+use crate::statistics::ResourceMetric;
+
+// instructions are charged per increment of 10000
+const INSTRUCTIONS_INCREMENT: i64 = 10_000;
+// size of the transaction result added to the historical data fee
+const TX_BASE_RESULT_SIZE: u32 = 300;
+
+// config settings needed to estimate resource fees
+pub const FEE_CONFIG_SETTINGS: [ConfigSettingId; 6] = [
+    ConfigSettingId::ContractComputeV0,
+    ConfigSettingId::ContractLedgerCostV0,
+    ConfigSettingId::ContractLedgerCostExtV0,
+    ConfigSettingId::ContractHistoricalDataV0,
+    ConfigSettingId::ContractEventsV0,
+    ConfigSettingId::ContractBandwidthV0,
+];
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FeeConfig {
+    pub fee_per_instruction_increment: i64,
+    pub fee_per_disk_read_entry: i64,
+    pub fee_per_write_entry: i64,
+    pub fee_per_disk_read_1kb: i64,
+    pub fee_per_write_1kb: i64,
+    pub fee_per_historical_1kb: i64,
+    pub fee_per_contract_event_1kb: i64,
+    pub fee_per_transaction_size_1kb: i64,
+}
+
+impl FeeConfig {
+    // build from the network config settings, None if one is missing
+    pub fn from_settings(settings: &[ConfigSettingEntry]) -> Option<Self> {
+        let mut compute = None;
+        let mut cost = None;
+        let mut cost_ext = None;
+        let mut historical = None;
+        let mut events = None;
+        let mut bandwidth = None;
+        for setting in settings {
+            match setting {
+                ConfigSettingEntry::ContractComputeV0(v) => compute = Some(v),
+                ConfigSettingEntry::ContractLedgerCostV0(v) => cost = Some(v),
+                ConfigSettingEntry::ContractLedgerCostExtV0(v) => cost_ext = Some(v),
+                ConfigSettingEntry::ContractHistoricalDataV0(v) => historical = Some(v),
+                ConfigSettingEntry::ContractEventsV0(v) => events = Some(v),
+                ConfigSettingEntry::ContractBandwidthV0(v) => bandwidth = Some(v),
+                _ => {}
+            }
+        }
+        Some(FeeConfig {
+            fee_per_instruction_increment: compute?.fee_rate_per_instructions_increment,
+            fee_per_disk_read_entry: cost?.fee_disk_read_ledger_entry,
+            fee_per_write_entry: cost?.fee_write_ledger_entry,
+            fee_per_disk_read_1kb: cost?.fee_disk_read1_kb,
+            fee_per_write_1kb: cost_ext?.fee_write1_kb,
+            fee_per_historical_1kb: historical?.fee_historical1_kb,
+            fee_per_contract_event_1kb: events?.fee_contract_events1_kb,
+            fee_per_transaction_size_1kb: bandwidth?.fee_tx_size1_kb,
+        })
+    }
+
+    // same algorithm as the host compute_transaction_resource_fee, plus the
+    // rent fee which the host computes separately
+    pub fn estimate(&self, r: &FeeResources) -> FeeEstimate {
+        FeeEstimate {
+            compute: fee_per_increment(
+                r.instructions as i64,
+                self.fee_per_instruction_increment,
+                INSTRUCTIONS_INCREMENT,
+            ),
+            read_entries: self
+                .fee_per_disk_read_entry
+                .saturating_mul(r.disk_read_entries as i64),
+            write_entries: self
+                .fee_per_write_entry
+                .saturating_mul(r.write_entries as i64),
+            read_bytes: fee_per_increment(
+                r.disk_read_bytes as i64,
+                self.fee_per_disk_read_1kb,
+                DATA_SIZE_1KB_INCREMENT,
+            ),
+            write_bytes: fee_per_increment(
+                r.write_bytes as i64,
+                self.fee_per_write_1kb,
+                DATA_SIZE_1KB_INCREMENT,
+            ),
+            historical: fee_per_increment(
+                r.transaction_size_bytes.saturating_add(TX_BASE_RESULT_SIZE) as i64,
+                self.fee_per_historical_1kb,
+                DATA_SIZE_1KB_INCREMENT,
+            ),
+            bandwidth: fee_per_increment(
+                r.transaction_size_bytes as i64,
+                self.fee_per_transaction_size_1kb,
+                DATA_SIZE_1KB_INCREMENT,
+            ),
+            events: fee_per_increment(
+                r.contract_events_size_bytes as i64,
+                self.fee_per_contract_event_1kb,
+                DATA_SIZE_1KB_INCREMENT,
+            ),
+            rent: r.rent_fee,
+        }
+    }
+}
+
+// resources charged by the fee algorithm, edit the fields of a measured
+// sample to cost a variant without resubmitting it
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FeeResources {
+    pub instructions: u32,
+    pub disk_read_entries: u32,
+    pub write_entries: u32,
+    pub disk_read_bytes: u32,
+    pub write_bytes: u32,
+    pub transaction_size_bytes: u32,
+    // contract events plus the return value
+    pub contract_events_size_bytes: u32,
+    pub rent_fee: i64,
+}
+
+impl FeeResources {
+    pub fn from_metric(metric: &ResourceMetric) -> Self {
+        FeeResources {
+            instructions: metric.cpu_insns.unwrap_or(0).min(u32::MAX as u64) as u32,
+            disk_read_entries: disk_read_entries(&metric.footprint) as u32,
+            write_entries: metric.entry_writes.unwrap_or(0) as u32,
+            disk_read_bytes: metric.read_bytes.unwrap_or(0),
+            write_bytes: metric.write_bytes.unwrap_or(0),
+            transaction_size_bytes: metric.min_txn_bytes.unwrap_or(0) as u32,
+            contract_events_size_bytes: metric.events_bytes.unwrap_or(0) as u32,
+            rent_fee: metric.rent_fee.unwrap_or(0),
+        }
+    }
+}
+
+// live soroban entries are not read from disk, only classic and restored
+// entries of the footprint are
+pub(crate) fn disk_read_entries(footprint: &[FootprintEntry]) -> usize {
+    footprint
+        .iter()
+        .filter(|e| {
+            matches!(
+                e.kind,
+                EntryKind::Account | EntryKind::Trustline | EntryKind::Other
+            ) || e.change == EntryChange::Restored
+        })
+        .count()
+}
+
+// stroops per fee component
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FeeEstimate {
+    pub compute: i64,
+    pub read_entries: i64,
+    pub write_entries: i64,
+    pub read_bytes: i64,
+    pub write_bytes: i64,
+    pub historical: i64,
+    pub bandwidth: i64,
+    pub events: i64,
+    pub rent: i64,
+}
+
+impl FeeEstimate {
+    pub fn non_refundable(&self) -> i64 {
+        self.compute
+            .saturating_add(self.read_entries)
+            .saturating_add(self.write_entries)
+            .saturating_add(self.read_bytes)
+            .saturating_add(self.write_bytes)
+            .saturating_add(self.historical)
+            .saturating_add(self.bandwidth)
+    }
+
+    pub fn refundable(&self) -> i64 {
+        self.events.saturating_add(self.rent)
+    }
+
+    pub fn total(&self) -> i64 {
+        self.non_refundable().saturating_add(self.refundable())
+    }
+
+    // (name, stroops) in display order
+    pub fn components(&self) -> [(&'static str, i64); 9] {
+        [
+            ("compute", self.compute),
+            ("read_entries", self.read_entries),
+            ("write_entries", self.write_entries),
+            ("read_bytes", self.read_bytes),
+            ("write_bytes", self.write_bytes),
+            ("historical", self.historical),
+            ("bandwidth", self.bandwidth),
+            ("events", self.events),
+            ("rent", self.rent),
+        ]
+    }
+}
+
+fn fee_per_increment(value: i64, fee_rate: i64, increment: i64) -> i64 {
+    div_ceil(value.saturating_mul(fee_rate), increment.max(1))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::footprint::test::entry;
+
+    fn config() -> FeeConfig {
+        FeeConfig {
+            fee_per_instruction_increment: 1000,
+            fee_per_disk_read_entry: 2000,
+            fee_per_write_entry: 4000,
+            fee_per_disk_read_1kb: 1500,
+            fee_per_write_1kb: 3000,
+            fee_per_historical_1kb: 300,
+            fee_per_contract_event_1kb: 200,
+            fee_per_transaction_size_1kb: 900,
+        }
+    }
+
+    #[test]
+    fn test_estimate() {
+        let resources = FeeResources {
+            instructions: 10_123_456,
+            disk_read_entries: 30,
+            write_entries: 10,
+            disk_read_bytes: 25_600,
+            write_bytes: 10_340,
+            transaction_size_bytes: 35_721,
+            contract_events_size_bytes: 321_654,
+            rent_fee: 777,
+        };
+        let fee = config().estimate(&resources);
+        // ceil(value * rate / increment) for each component, as the host
+        // compute_transaction_resource_fee does
+        assert_eq!(
+            fee,
+            FeeEstimate {
+                compute: 1_012_346,
+                read_entries: 60_000,
+                write_entries: 40_000,
+                read_bytes: 37_500,
+                write_bytes: 30_293,
+                // (35_721 + 300) * 300 / 1024
+                historical: 10_554,
+                bandwidth: 31_396,
+                events: 62_824,
+                rent: 777,
+            }
+        );
+        assert_eq!(fee.non_refundable(), 1_222_089);
+        assert_eq!(fee.refundable(), 63_601);
+        assert_eq!(fee.total(), 1_285_690);
+    }
+
+    #[test]
+    fn test_estimate_empty() {
+        // only the base result size is charged for an empty transaction
+        let fee = config().estimate(&FeeResources::default());
+        assert_eq!(
+            fee,
+            FeeEstimate {
+                historical: 88,
+                ..Default::default()
+            }
+        );
+        assert_eq!(fee.total(), 88);
+    }
+
+    #[test]
+    fn test_from_metric() {
+        let metric = ResourceMetric {
+            cpu_insns: Some(u64::MAX),
+            entry_writes: Some(2),
+            read_bytes: Some(1000),
+            write_bytes: Some(200),
+            min_txn_bytes: Some(500),
+            // already includes the return value
+            events_bytes: Some(120),
+            rent_fee: Some(50),
+            footprint: vec![
+                entry(EntryKind::Account, EntryChange::Unchanged),
+                entry(EntryKind::Trustline, EntryChange::Updated),
+                entry(EntryKind::Other, EntryChange::Unchanged),
+                entry(EntryKind::ContractInstance, EntryChange::Unchanged),
+                entry(EntryKind::ContractCode, EntryChange::Unchanged),
+                entry(EntryKind::PersistentData, EntryChange::Updated),
+                entry(EntryKind::PersistentData, EntryChange::Restored),
+                entry(EntryKind::ContractCode, EntryChange::Restored),
+            ],
+            ..Default::default()
+        };
+        let resources = FeeResources::from_metric(&metric);
+        // the classic entries and the two restored ones
+        assert_eq!(resources.disk_read_entries, 5);
+        assert_eq!(resources.instructions, u32::MAX);
+        assert_eq!(resources.write_entries, 2);
+        assert_eq!(resources.disk_read_bytes, 1000);
+        assert_eq!(resources.write_bytes, 200);
+        assert_eq!(resources.transaction_size_bytes, 500);
+        assert_eq!(resources.contract_events_size_bytes, 120);
+        assert_eq!(resources.rent_fee, 50);
+    }
+}
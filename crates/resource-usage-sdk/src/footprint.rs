@@ -0,0 +1,424 @@
+use std::collections::HashMap;
+
+use soroban_client::{
+    hashing::{HashingBehavior, Sha256Hasher},
+    xdr::{
+        ContractDataDurability, LedgerEntryChange, LedgerEntryData, LedgerFootprint, LedgerKey,
+        Limits, ScVal, TransactionMetaV4, WriteXdr,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntryKind {
+    ContractInstance,
+    PersistentData,
+    TemporaryData,
+    ContractCode,
+    Account,
+    Trustline,
+    Other,
+}
+
+impl EntryKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryKind::ContractInstance => "instance",
+            EntryKind::PersistentData => "persistent",
+            EntryKind::TemporaryData => "temporary",
+            EntryKind::ContractCode => "code",
+            EntryKind::Account => "account",
+            EntryKind::Trustline => "trustline",
+            EntryKind::Other => "other",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntryAccess {
+    ReadOnly,
+    ReadWrite,
+}
+
+impl EntryAccess {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryAccess::ReadOnly => "read_only",
+            EntryAccess::ReadWrite => "read_write",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntryChange {
+    Created,
+    Updated,
+    Deleted,
+    Restored,
+    Unchanged,
+}
+
+impl EntryChange {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryChange::Created => "created",
+            EntryChange::Updated => "updated",
+            EntryChange::Deleted => "deleted",
+            EntryChange::Restored => "restored",
+            EntryChange::Unchanged => "unchanged",
+        }
+    }
+}
+
+// one ledger key of the transaction footprint
+#[derive(Debug, Clone)]
+pub struct FootprintEntry {
+    pub key: LedgerKey,
+    pub kind: EntryKind,
+    pub access: EntryAccess,
+    // contract or account owning the entry
+    pub owner: Option<String>,
+    // decoded contract data key, None for non contract-data entries
+    pub key_val: Option<ScVal>,
+    // xdr size of the entry data, None when not seen in the meta changes
+    pub size_before: Option<usize>,
+    pub size_after: Option<usize>,
+    pub change: EntryChange,
+    // live_until ledger from the ttl entry, None when the ttl was not touched
+    pub live_until_before: Option<u32>,
+    pub live_until_after: Option<u32>,
+}
+
+impl FootprintEntry {
+    // ttl was extended by this transaction
+    pub fn ttl_extended(&self) -> bool {
+        match (self.live_until_before, self.live_until_after) {
+            (Some(before), Some(after)) => after > before,
+            (None, Some(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Default)]
+struct KeyChange {
+    before: Option<usize>,
+    after: Option<usize>,
+    change: Option<EntryChange>,
+}
+
+#[derive(Default)]
+struct TtlChange {
+    before: Option<u32>,
+    after: Option<u32>,
+}
+
+// list every footprint key with its size before/after from the operation changes
+pub fn footprint_entries(
+    footprint: &LedgerFootprint,
+    meta: &TransactionMetaV4,
+    limits: Limits,
+) -> Vec<FootprintEntry> {
+    let (changes, ttls) = collect_changes(meta, limits.clone());
+    let read_only = footprint
+        .read_only
+        .iter()
+        .map(|k| (k, EntryAccess::ReadOnly));
+    let read_write = footprint
+        .read_write
+        .iter()
+        .map(|k| (k, EntryAccess::ReadWrite));
+    read_only
+        .chain(read_write)
+        .map(|(key, access)| {
+            let (kind, owner, key_val) = describe_key(key);
+            let (size_before, size_after, change) = match changes.get(key) {
+                Some(c) => (
+                    c.before,
+                    c.after,
+                    c.change.unwrap_or(EntryChange::Unchanged),
+                ),
+                None => (None, None, EntryChange::Unchanged),
+            };
+            let (live_until_before, live_until_after) =
+                match key_hash(key, limits.clone()).and_then(|hash| ttls.get(&hash)) {
+                    Some(ttl) => (ttl.before, ttl.after),
+                    None => (None, None),
+                };
+            FootprintEntry {
+                key: key.clone(),
+                kind,
+                access,
+                owner,
+                key_val,
+                size_before,
+                size_after,
+                change,
+                live_until_before,
+                live_until_after,
+            }
+        })
+        .collect()
+}
+
+// ttl entries are keyed by the sha256 of the xdr ledger key
+fn key_hash(key: &LedgerKey, limits: Limits) -> Option<[u8; 32]> {
+    key.to_xdr(limits).ok().map(Sha256Hasher::hash)
+}
+
+fn collect_changes(
+    meta: &TransactionMetaV4,
+    limits: Limits,
+) -> (HashMap<LedgerKey, KeyChange>, HashMap<[u8; 32], TtlChange>) {
+    let mut map: HashMap<LedgerKey, KeyChange> = HashMap::new();
+    let mut ttls: HashMap<[u8; 32], TtlChange> = HashMap::new();
+    let data_len = |data: &LedgerEntryData| data.to_xdr(limits.clone()).map(|b| b.len()).ok();
+    for op in meta.operations.iter() {
+        for change in op.changes.iter() {
+            if let Some((hash, live_until, is_state)) = ttl_change(change) {
+                let ttl = ttls.entry(hash).or_default();
+                if is_state {
+                    ttl.before = Some(live_until);
+                } else {
+                    ttl.after = Some(live_until);
+                }
+                continue;
+            }
+            match change {
+                LedgerEntryChange::State(entry) => {
+                    let c = map.entry(entry.to_key()).or_default();
+                    c.before = data_len(&entry.data);
+                }
+                LedgerEntryChange::Created(entry) => {
+                    let c = map.entry(entry.to_key()).or_default();
+                    c.after = data_len(&entry.data);
+                    c.change = Some(EntryChange::Created);
+                }
+                LedgerEntryChange::Updated(entry) => {
+                    let c = map.entry(entry.to_key()).or_default();
+                    c.after = data_len(&entry.data);
+                    // an update right after a restore is still a restore
+                    if c.change != Some(EntryChange::Restored) {
+                        c.change = Some(EntryChange::Updated);
+                    }
+                }
+                LedgerEntryChange::Restored(entry) => {
+                    let c = map.entry(entry.to_key()).or_default();
+                    c.after = data_len(&entry.data);
+                    c.change = Some(EntryChange::Restored);
+                }
+                LedgerEntryChange::Removed(key) => {
+                    let c = map.entry(key.clone()).or_default();
+                    c.after = None;
+                    c.change = Some(EntryChange::Deleted);
+                }
+            }
+        }
+    }
+    (map, ttls)
+}
+
+// (key hash, live_until, is_state) of a ttl entry change
+fn ttl_change(change: &LedgerEntryChange) -> Option<([u8; 32], u32, bool)> {
+    let (entry, is_state) = match change {
+        LedgerEntryChange::State(entry) => (entry, true),
+        LedgerEntryChange::Created(entry)
+        | LedgerEntryChange::Updated(entry)
+        | LedgerEntryChange::Restored(entry) => (entry, false),
+        LedgerEntryChange::Removed(_) => return None,
+    };
+    match &entry.data {
+        LedgerEntryData::Ttl(ttl) => Some((ttl.key_hash.0, ttl.live_until_ledger_seq, is_state)),
+        _ => None,
+    }
+}
+
+fn describe_key(key: &LedgerKey) -> (EntryKind, Option<String>, Option<ScVal>) {
+    match key {
+        LedgerKey::ContractData(data) => {
+            let kind = match (&data.key, data.durability) {
+                (ScVal::LedgerKeyContractInstance, _) => EntryKind::ContractInstance,
+                (_, ContractDataDurability::Persistent) => EntryKind::PersistentData,
+                (_, ContractDataDurability::Temporary) => EntryKind::TemporaryData,
+            };
+            let key_val = match kind {
+                EntryKind::ContractInstance => None,
+                _ => Some(data.key.clone()),
+            };
+            (kind, Some(data.contract.to_string()), key_val)
+        }
+        LedgerKey::ContractCode(code) => (
+            EntryKind::ContractCode,
+            Some(hex::encode(code.hash.0)),
+            None,
+        ),
+        LedgerKey::Account(account) => (
+            EntryKind::Account,
+            Some(account.account_id.to_string()),
+            None,
+        ),
+        LedgerKey::Trustline(trustline) => (
+            EntryKind::Trustline,
+            Some(trustline.account_id.to_string()),
+            None,
+        ),
+        _ => (EntryKind::Other, None, None),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use soroban_client::xdr::{
+        AccountId, ContractDataEntry, ContractId, ExtensionPoint, Hash, LedgerEntry,
+        LedgerEntryExt, LedgerKeyAccount, LedgerKeyContractData, OperationMetaV2, PublicKey,
+        ScAddress, ScSymbol, TtlEntry, Uint256,
+    };
+
+    // read-only entry of `kind` with an account key, for the tests that only
+    // look at the kind and change
+    pub(crate) fn entry(kind: EntryKind, change: EntryChange) -> FootprintEntry {
+        FootprintEntry {
+            key: LedgerKey::Account(LedgerKeyAccount {
+                account_id: AccountId(PublicKey::PublicKeyTypeEd25519(Uint256([0; 32]))),
+            }),
+            kind,
+            access: EntryAccess::ReadOnly,
+            owner: None,
+            key_val: None,
+            size_before: None,
+            size_after: None,
+            change,
+            live_until_before: None,
+            live_until_after: None,
+        }
+    }
+
+    fn data_key(name: &str, durability: ContractDataDurability) -> LedgerKey {
+        LedgerKey::ContractData(LedgerKeyContractData {
+            contract: ScAddress::Contract(ContractId(Hash([7; 32]))),
+            key: ScVal::Symbol(ScSymbol(name.try_into().unwrap())),
+            durability,
+        })
+    }
+
+    fn ledger_entry(data: LedgerEntryData) -> LedgerEntry {
+        LedgerEntry {
+            last_modified_ledger_seq: 1,
+            data,
+            ext: LedgerEntryExt::V0,
+        }
+    }
+
+    fn data_entry(key: &LedgerKey, val: ScVal) -> LedgerEntry {
+        let LedgerKey::ContractData(key) = key else {
+            panic!("not a contract data key");
+        };
+        ledger_entry(LedgerEntryData::ContractData(ContractDataEntry {
+            ext: ExtensionPoint::V0,
+            contract: key.contract.clone(),
+            key: key.key.clone(),
+            durability: key.durability,
+            val,
+        }))
+    }
+
+    fn ttl_entry(key: &LedgerKey, live_until: u32) -> LedgerEntry {
+        ledger_entry(LedgerEntryData::Ttl(TtlEntry {
+            key_hash: Hash(key_hash(key, Limits::none()).unwrap()),
+            live_until_ledger_seq: live_until,
+        }))
+    }
+
+    fn meta(changes: Vec<LedgerEntryChange>) -> TransactionMetaV4 {
+        TransactionMetaV4 {
+            ext: ExtensionPoint::V0,
+            tx_changes_before: Default::default(),
+            operations: vec![OperationMetaV2 {
+                ext: ExtensionPoint::V0,
+                changes: changes.try_into().unwrap(),
+                events: Default::default(),
+            }]
+            .try_into()
+            .unwrap(),
+            tx_changes_after: Default::default(),
+            soroban_meta: None,
+            events: Default::default(),
+            diagnostic_events: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_footprint_entries() {
+        let restored = data_key("restored", ContractDataDurability::Persistent);
+        let updated = data_key("updated", ContractDataDurability::Persistent);
+        let temp = data_key("temp", ContractDataDurability::Temporary);
+        let instance = LedgerKey::ContractData(LedgerKeyContractData {
+            contract: ScAddress::Contract(ContractId(Hash([7; 32]))),
+            key: ScVal::LedgerKeyContractInstance,
+            durability: ContractDataDurability::Persistent,
+        });
+        let footprint = LedgerFootprint {
+            read_only: vec![instance, temp].try_into().unwrap(),
+            read_write: vec![restored.clone(), updated.clone()].try_into().unwrap(),
+        };
+        let meta = meta(vec![
+            // restored then written by the invocation
+            LedgerEntryChange::Restored(data_entry(&restored, ScVal::U32(1))),
+            LedgerEntryChange::Restored(ttl_entry(&restored, 200)),
+            LedgerEntryChange::State(data_entry(&restored, ScVal::U32(1))),
+            LedgerEntryChange::Updated(data_entry(&restored, ScVal::U32(2))),
+            LedgerEntryChange::State(data_entry(&updated, ScVal::U32(1))),
+            LedgerEntryChange::Updated(data_entry(&updated, ScVal::U64(1))),
+            LedgerEntryChange::State(ttl_entry(&updated, 100)),
+            LedgerEntryChange::Updated(ttl_entry(&updated, 150)),
+            // ttl of a key outside the footprint
+            LedgerEntryChange::Updated(ttl_entry(
+                &data_key("other", ContractDataDurability::Persistent),
+                300,
+            )),
+        ]);
+
+        let entries = footprint_entries(&footprint, &meta, Limits::none());
+        assert_eq!(entries.len(), 4);
+
+        let instance = &entries[0];
+        assert_eq!(instance.kind, EntryKind::ContractInstance);
+        assert_eq!(instance.access, EntryAccess::ReadOnly);
+        assert_eq!(instance.key_val, None);
+
+        let temp = &entries[1];
+        assert_eq!(temp.kind, EntryKind::TemporaryData);
+        assert_eq!(temp.change, EntryChange::Unchanged);
+        assert_eq!((temp.size_before, temp.size_after), (None, None));
+        assert_eq!(
+            (temp.live_until_before, temp.live_until_after),
+            (None, None)
+        );
+        assert!(!temp.ttl_extended());
+
+        let restored = &entries[2];
+        assert_eq!(restored.kind, EntryKind::PersistentData);
+        assert_eq!(restored.access, EntryAccess::ReadWrite);
+        assert_eq!(restored.change, EntryChange::Restored);
+        assert_eq!(
+            restored.key_val,
+            Some(ScVal::Symbol(ScSymbol("restored".try_into().unwrap())))
+        );
+        assert!(restored.size_before.is_some());
+        assert_eq!(restored.size_after, restored.size_before);
+        assert_eq!(restored.live_until_before, None);
+        assert_eq!(restored.live_until_after, Some(200));
+        assert!(restored.ttl_extended());
+
+        let updated = &entries[3];
+        assert_eq!(updated.change, EntryChange::Updated);
+        // u64 takes 4 more bytes than u32
+        assert_eq!(
+            updated.size_after.unwrap(),
+            updated.size_before.unwrap() + 4
+        );
+        assert_eq!(updated.live_until_before, Some(100));
+        assert_eq!(updated.live_until_after, Some(150));
+        assert!(updated.ttl_extended());
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+use soroban_client::xdr::{ScSpecEntry, ScVal};
+
+use crate::{rpc_server::ContractStore, scval_tools, spec};
+
+// how samples of a function are split by one of its arguments
+#[derive(Debug, Clone)]
+pub enum ArgGroup {
+    // one group per decoded value
+    Value,
+    // numeric values bucketed by the given width, e.g. 0..1000
+    Bucket(u128),
+    // length of a vec, map, bytes, string or symbol
+    Len,
+}
+
+// (contract, function) -> (argument name, grouping)
+pub type GroupConfig = HashMap<(String, String), (String, ArgGroup)>;
+
+// split every configured function into one entry per argument group,
+// e.g. "transfer" into "transfer[amount=0..1000]"
+pub fn group_store(
+    store: &ContractStore,
+    specs: &HashMap<String, Vec<ScSpecEntry>>,
+    groups: &GroupConfig,
+) -> ContractStore {
+    let mut res: ContractStore = HashMap::new();
+    for (contract, funcs) in store {
+        let contract_entry = res.entry(contract.clone()).or_default();
+        for (func, samples) in funcs {
+            let group = groups.get(&(contract.clone(), func.clone()));
+            let spec = specs.get(contract);
+            for sample in samples {
+                let label = match (group, spec) {
+                    (Some((arg, group)), Some(spec)) => {
+                        arg_label(spec, func, arg, group, &sample.args)
+                            .map(|label| format!("{func}[{arg}={label}]"))
+                    }
+                    _ => None,
+                };
+                contract_entry
+                    .entry(label.unwrap_or_else(|| func.clone()))
+                    .or_default()
+                    .push(sample.clone());
+            }
+        }
+    }
+    res
+}
+
+fn arg_label(
+    spec: &[ScSpecEntry],
+    func: &str,
+    arg: &str,
+    group: &ArgGroup,
+    args: &[ScVal],
+) -> Option<String> {
+    let function = spec::find_function(spec, func)?;
+    let index = function
+        .inputs
+        .iter()
+        .position(|input| input.name.to_string() == arg)?;
+    let val = args.get(index)?;
+    let decoded = scval_tools::scval_to_json_with_spec(val, &function.inputs[index].type_, spec);
+    let label = match group {
+        ArgGroup::Value => value_label(&decoded),
+        ArgGroup::Bucket(width) => {
+            let width = (*width).clamp(1, i128::MAX as u128) as i128;
+            match scval_tools::scval_as_i128(val) {
+                Some(n) => {
+                    let low = n.div_euclid(width).saturating_mul(width);
+                    format!("{low}..{}", low.saturating_add(width))
+                }
+                None => value_label(&decoded),
+            }
+        }
+        ArgGroup::Len => match val {
+            ScVal::Vec(Some(v)) => v.len().to_string(),
+            ScVal::Map(Some(m)) => m.len().to_string(),
+            ScVal::Bytes(b) => b.len().to_string(),
+            ScVal::String(s) => s.len().to_string(),
+            ScVal::Symbol(s) => s.len().to_string(),
+            _ => value_label(&decoded),
+        },
+    };
+    Some(label)
+}
+
+fn value_label(v: &Value) -> String {
+    match v {
+        Value::String(s) => s.clone(),
+        _ => v.to_string(),
+    }
+}
@@ -0,0 +1,150 @@
+use soroban_client::xdr::{ConfigSettingEntry, ConfigSettingId};
+use std::collections::HashMap;
+
+use crate::capacity::{self, LedgerCapacity};
+use crate::rpc_server::ContractStore;
+use crate::show;
+
+// config settings holding the ledger-wide limits
+pub const LEDGER_LIMIT_SETTINGS: [ConfigSettingId; 3] = [
+    ConfigSettingId::ContractComputeV0,
+    ConfigSettingId::ContractLedgerCostV0,
+    ConfigSettingId::ContractBandwidthV0,
+];
+
+// ledger-wide limits keyed like show::stellar_limits_config, None if one
+// of the settings is missing
+pub fn ledger_limits_from_settings(
+    settings: &[ConfigSettingEntry],
+) -> Option<HashMap<&'static str, u64>> {
+    let mut compute = None;
+    let mut cost = None;
+    let mut bandwidth = None;
+    for setting in settings {
+        match setting {
+            ConfigSettingEntry::ContractComputeV0(v) => compute = Some(v),
+            ConfigSettingEntry::ContractLedgerCostV0(v) => cost = Some(v),
+            ConfigSettingEntry::ContractBandwidthV0(v) => bandwidth = Some(v),
+            _ => {}
+        }
+    }
+    let (compute, cost, bandwidth) = (compute?, cost?, bandwidth?);
+    Some(HashMap::from([
+        ("cpu_insns", compute.ledger_max_instructions.max(0) as u64),
+        ("entry_reads", cost.ledger_max_disk_read_entries as u64),
+        ("entry_writes", cost.ledger_max_write_ledger_entries as u64),
+        ("read_bytes", cost.ledger_max_disk_read_bytes as u64),
+        ("write_bytes", cost.ledger_max_write_bytes as u64),
+        ("min_txn_bytes", bandwidth.ledger_max_txs_size_bytes as u64),
+    ]))
+}
+
+#[derive(Debug, Clone)]
+pub struct Headroom {
+    pub function: String,
+    pub times: usize,
+    // metric whose max is the closest to its transaction limit
+    pub binding: &'static str,
+    // max / limit of the binding metric
+    pub usage: f64,
+    // identical calls fitting in one ledger at their max usage, and the
+    // ledger limit reached first
+    pub per_ledger: Option<u64>,
+    pub ledger_binding: Option<&'static str>,
+}
+
+impl Headroom {
+    // remaining share of the binding limit, in percent
+    pub fn headroom(&self) -> f64 {
+        (1.0 - self.usage) * 100.0
+    }
+}
+
+// one entry per function of `contract_id`, tightest first. `tx_limits` are
+// the per-transaction network limits, e.g. StellarRpcServer::get_tx_limits
+pub fn headroom(
+    contract_id: &str,
+    store: &ContractStore,
+    tx_limits: &HashMap<&'static str, u64>,
+    ledger: &LedgerCapacity,
+) -> Vec<Headroom> {
+    let mut statistics = show::calc_statistics(store);
+    statistics.retain(|contract, _| contract == contract_id);
+    let funcs = show::load_table_data(&statistics, tx_limits);
+
+    let mut res: Vec<Headroom> = funcs
+        .into_iter()
+        .filter_map(|f| {
+            let (binding, usage) = f
+                .rows
+                .iter()
+                .filter_map(|(key, limit, _, max, _, _)| {
+                    Some((*key, *max as f64 / (*limit)? as f64))
+                })
+                .max_by(|a, b| a.1.total_cmp(&b.1))?;
+            let mut maxes: HashMap<&'static str, f64> = f
+                .rows
+                .iter()
+                .map(|(key, _, _, max, _, _)| (*key, *max as f64))
+                .collect();
+            let disk_reads = capacity::disk_reads(store, contract_id, &f.func)
+                .max()
+                .unwrap_or(0);
+            maxes.insert("entry_reads", disk_reads as f64);
+            let ledger_fit = ledger.fit(&maxes);
+            Some(Headroom {
+                function: f.func,
+                times: f.times,
+                binding,
+                usage,
+                per_ledger: ledger_fit.map(|(fit, _)| fit as u64),
+                ledger_binding: ledger_fit.map(|(_, key)| key),
+            })
+        })
+        .collect();
+    res.sort_by(|a, b| b.usage.total_cmp(&a.usage));
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::footprint::test::entry;
+    use crate::footprint::{EntryChange, EntryKind};
+    use crate::statistics::ResourceMetric;
+
+    #[test]
+    fn test_ledger_fit() {
+        let sample = ResourceMetric {
+            cpu_insns: Some(50_000_000),
+            entry_reads: Some(3),
+            footprint: vec![
+                entry(EntryKind::Account, EntryChange::Unchanged),
+                entry(EntryKind::ContractInstance, EntryChange::Unchanged),
+                entry(EntryKind::PersistentData, EntryChange::Unchanged),
+            ],
+            ..Default::default()
+        };
+        let store = ContractStore::from([(
+            "C1".to_string(),
+            HashMap::from([("transfer".to_string(), vec![sample])]),
+        )]);
+        let ledger = LedgerCapacity {
+            limits: HashMap::from([("cpu_insns", 100_000_000), ("entry_reads", 20)]),
+            max_tx_count: Some(1_000),
+            clusters: 4,
+            ..Default::default()
+        };
+        let res = headroom("C1", &store, &show::stellar_limits_config(), &ledger);
+        assert_eq!(res.len(), 1);
+        // 4 clusters of 100M instructions, only the account is a disk read
+        assert_eq!(res[0].per_ledger, Some(8));
+        assert_eq!(res[0].ledger_binding, Some("cpu_insns"));
+
+        // the binding metric follows the given transaction limits
+        let tx_limits = HashMap::from([("cpu_insns", 50_000_000), ("entry_reads", 100)]);
+        let res = headroom("C1", &store, &tx_limits, &ledger);
+        assert_eq!(res[0].binding, "cpu_insns");
+        assert_eq!(res[0].usage, 1.0);
+    }
+}
@@ -1,8 +1,51 @@
+mod auth;
+mod capacity;
+mod client;
+mod config;
 mod error;
+mod fee;
+mod footprint;
+mod grouping;
+mod headroom;
+#[cfg(any(test, feature = "mock"))]
+mod mock;
+#[cfg(feature = "sqlite")]
+mod persist;
+mod prometheus;
+mod rent;
 mod rpc_server;
-mod scval_tools;
+mod scaling;
+mod scenario;
+pub mod scval_tools;
 mod show;
+mod signer;
+mod spec;
 mod statistics;
+mod trace;
+mod trend;
+mod wasm;
+mod watcher;
 
+pub use capacity::{
+    capacity, workload_capacity, FunctionCapacity, LedgerCapacity, WorkloadCapacity,
+};
+pub use client::{Client, SOURCE_ACCOUNT};
+pub use config::{Network, NetworkConfig};
 pub use error::Error;
-pub use rpc_server::StellarRpcServer;
+pub use fee::{FeeConfig, FeeEstimate, FeeResources};
+pub use grouping::ArgGroup;
+pub use headroom::{headroom, Headroom};
+#[cfg(any(test, feature = "mock"))]
+pub use mock::{MockOutcome, MockResources, MockRpc};
+#[cfg(feature = "sqlite")]
+pub use persist::{MetricDb, RunContext, RunInfo, SampleFilter, StoredSample};
+pub use prometheus::{MetricsRegistry, PrometheusExporter};
+pub use rent::RentConfig;
+pub use rpc_server::{ContractStore, FunctionStore, StellarRpcServer};
+pub use scaling::ScalingReport;
+pub use scenario::{ArgSpec, Scenario};
+pub use show::{print_fees, print_store, print_trend};
+pub use signer::{ContractAuthFn, ExternalSigner, SignFn, Signer, SignerRegistry};
+pub use trace::TraceEvent;
+pub use trend::{trends, write_svg_charts, FunctionTrend, MetricTrend, TrendPoint};
+pub use wasm::WasmReport;
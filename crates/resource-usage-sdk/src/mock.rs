@@ -0,0 +1,835 @@
+use serde_json::{json, Value};
+use soroban_client::hashing::{HashingBehavior, Sha256Hasher};
+use soroban_client::xdr::{
+    AccountEntry, AccountEntryExt, AccountId, ContractDataDurability, ContractDataEntry,
+    ContractEvent, ContractEventBody, ContractEventType, ContractEventV0, ContractId,
+    DiagnosticEvent, ExtensionPoint, Hash, HostFunction, InvokeHostFunctionResult, LedgerEntry,
+    LedgerEntryChange, LedgerEntryData, LedgerEntryExt, LedgerFootprint, LedgerKey,
+    LedgerKeyAccount, Limits, OperationBody, OperationMetaV2, OperationResult, OperationResultTr,
+    ReadXdr, ScAddress, ScBytes, ScSymbol, ScVal, SequenceNumber, SorobanResources,
+    SorobanTransactionData, SorobanTransactionDataExt, SorobanTransactionMetaExt,
+    SorobanTransactionMetaExtV1, SorobanTransactionMetaV2, Thresholds, TransactionEnvelope,
+    TransactionMeta, TransactionMetaV4, TransactionResult, TransactionResultExt,
+    TransactionResultResult, TtlEntry, WriteXdr,
+};
+use soroban_client::Options;
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::{statistics, watcher, Error, Network};
+
+// ledgers a created contract data or code entry stays live
+const ENTRY_TTL: u32 = 4096;
+
+// resources reported for every simulated and sent transaction
+#[derive(Debug, Clone)]
+pub struct MockResources {
+    pub cpu_insns: u64,
+    pub mem_bytes: u64,
+    pub read_bytes: u32,
+    pub write_bytes: u32,
+    pub non_refundable_fee: i64,
+    pub refundable_fee: i64,
+    pub rent_fee: i64,
+    pub fee_charged: i64,
+    // returned by contract invocations
+    pub return_value: ScVal,
+    // footprint of every simulation
+    pub footprint: LedgerFootprint,
+    // entries created by every sent transaction, contract data and code
+    // entries come with their ttl entry
+    pub created: Vec<LedgerEntryData>,
+    // contract events emitted by every sent transaction
+    pub events: Vec<ContractEvent>,
+}
+
+impl Default for MockResources {
+    fn default() -> Self {
+        Self {
+            cpu_insns: 1_000_000,
+            mem_bytes: 500_000,
+            read_bytes: 1_000,
+            write_bytes: 200,
+            non_refundable_fee: 5_000,
+            refundable_fee: 1_000,
+            rent_fee: 300,
+            fee_charged: 6_400,
+            return_value: ScVal::Void,
+            footprint: LedgerFootprint {
+                read_only: Default::default(),
+                read_write: Default::default(),
+            },
+            created: vec![],
+            events: vec![],
+        }
+    }
+}
+
+impl MockResources {
+    // create a persistent "balance" entry of `contract` in the read-write
+    // footprint and emit a "transfer" event from it
+    pub fn with_storage(mut self, contract: ContractId) -> Self {
+        let data = LedgerEntryData::ContractData(ContractDataEntry {
+            ext: ExtensionPoint::V0,
+            contract: ScAddress::Contract(contract.clone()),
+            key: symbol("balance"),
+            durability: ContractDataDurability::Persistent,
+            val: ScVal::U64(100),
+        });
+        let mut read_write = self.footprint.read_write.to_vec();
+        read_write.push(ledger_entry(data.clone(), 0).to_key());
+        self.footprint.read_write = read_write.try_into().unwrap_or_default();
+        self.created.push(data);
+        self.events.push(ContractEvent {
+            ext: ExtensionPoint::V0,
+            contract_id: Some(contract),
+            type_: ContractEventType::Contract,
+            body: ContractEventBody::V0(ContractEventV0 {
+                topics: vec![symbol("transfer")].try_into().unwrap_or_default(),
+                data: ScVal::U64(5),
+            }),
+        });
+        self
+    }
+}
+
+// what happens to the sent transactions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MockOutcome {
+    #[default]
+    Success,
+    Failed,
+    // accepted but never included in a ledger
+    Dropped,
+}
+
+#[derive(Debug)]
+struct MockTransaction {
+    hash: String,
+    envelope: TransactionEnvelope,
+    ledger: u32,
+    failed: bool,
+}
+
+#[derive(Debug)]
+struct MockState {
+    passphrase: String,
+    friendbot_url: String,
+    ledger: u32,
+    resources: MockResources,
+    // consumed before the built-in handler of the method
+    scripted: HashMap<String, VecDeque<Value>>,
+    entries: Vec<(LedgerKey, LedgerEntryData)>,
+    transactions: Vec<MockTransaction>,
+    outcome: MockOutcome,
+    // (method, params) of every call
+    requests: Vec<(String, Value)>,
+}
+
+// json-rpc server answering simulateTransaction, sendTransaction,
+// getTransaction(s), getLedgerEntries, getNetwork and getLatestLedger on a
+// local port, with a friendbot at /friendbot, every sent transaction is
+// included in its own ledger unless set_outcome says otherwise:
+//
+//   let mock = MockRpc::start()?;
+//   let server = StellarRpcServer::new(&mock.url(), MockRpc::options())?;
+#[derive(Debug, Clone)]
+pub struct MockRpc {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockRpc {
+    // standalone network passphrase
+    pub fn start() -> Result<Self, Error> {
+        Self::start_with(Network::Standalone.passphrase())
+    }
+
+    pub fn start_with(passphrase: &str) -> Result<Self, Error> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(MockState {
+            passphrase: passphrase.to_string(),
+            friendbot_url: format!("http://{addr}/friendbot"),
+            ledger: 100,
+            resources: MockResources::default(),
+            scripted: HashMap::new(),
+            entries: vec![],
+            transactions: vec![],
+            outcome: MockOutcome::default(),
+            requests: vec![],
+        }));
+        let shared = state.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let state = shared.clone();
+                thread::spawn(move || {
+                    let _ = respond(stream, &state);
+                });
+            }
+        });
+        Ok(Self { addr, state })
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn options() -> Options {
+        Options {
+            allow_http: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_resources(&self, resources: MockResources) {
+        self.lock().resources = resources;
+    }
+
+    // answer the next call of `method` with `result`, or with a json-rpc
+    // error when it is an object with a "code"
+    pub fn push_response(&self, method: &str, result: Value) {
+        self.lock()
+            .scripted
+            .entry(method.to_string())
+            .or_default()
+            .push_back(result);
+    }
+
+    // returned by getLedgerEntries for `key`
+    pub fn insert_entry(&self, key: LedgerKey, data: LedgerEntryData) {
+        let mut state = self.lock();
+        state.entries.retain(|(k, _)| *k != key);
+        state.entries.push((key, data));
+    }
+
+    // account returned by getLedgerEntries, as friendbot or a create
+    // account operation would leave it
+    pub fn fund(&self, address: &str) -> Result<(), Error> {
+        self.lock().fund(address)
+    }
+
+    // envelopes of the sent transactions, in order
+    pub fn sent(&self) -> Vec<TransactionEnvelope> {
+        self.lock()
+            .transactions
+            .iter()
+            .map(|tx| tx.envelope.clone())
+            .collect()
+    }
+
+    pub fn set_outcome(&self, outcome: MockOutcome) {
+        self.lock().outcome = outcome;
+    }
+
+    // methods called so far, in order, "friendbot" for friendbot requests
+    pub fn requests(&self) -> Vec<String> {
+        self.lock()
+            .requests
+            .iter()
+            .map(|(method, _)| method.clone())
+            .collect()
+    }
+
+    // params of every call of `method`, in order
+    pub fn params(&self, method: &str) -> Vec<Value> {
+        self.lock()
+            .requests
+            .iter()
+            .filter(|(m, _)| m == method)
+            .map(|(_, params)| params.clone())
+            .collect()
+    }
+
+    pub fn latest_ledger(&self) -> u32 {
+        self.lock().ledger
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn respond(stream: TcpStream, state: &Mutex<MockState>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line == "\r\n" {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    let mut body = vec![0u8; content_length];
+    reader.read_exact(&mut body)?;
+
+    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+    let response = match request_line.split_once("/friendbot?addr=") {
+        Some((_, rest)) => {
+            let address = rest.split_whitespace().next().unwrap_or_default();
+            state
+                .requests
+                .push(("friendbot".into(), json!({"addr": address})));
+            json!({"successful": state.fund(address).is_ok()})
+        }
+        None => {
+            let request: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+            let method = request["method"].as_str().unwrap_or_default();
+            match state.handle(method, &request["params"]) {
+                Ok(result) => json!({"jsonrpc": "2.0", "id": request["id"], "result": result}),
+                Err(error) => json!({"jsonrpc": "2.0", "id": request["id"], "error": error}),
+            }
+        }
+    };
+    drop(state);
+    let body = response.to_string();
+    let mut stream = reader.into_inner();
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+impl MockState {
+    fn handle(&mut self, method: &str, params: &Value) -> Result<Value, Value> {
+        self.requests.push((method.to_string(), params.clone()));
+        if let Some(result) = self.scripted.get_mut(method).and_then(|q| q.pop_front()) {
+            return match result.get("code") {
+                Some(_) => Err(result),
+                None => Ok(result),
+            };
+        }
+        match method {
+            "getNetwork" => Ok(json!({
+                "friendbotUrl": self.friendbot_url,
+                "passphrase": self.passphrase,
+                "protocolVersion": 23,
+            })),
+            "getLatestLedger" => Ok(json!({
+                "id": hex::encode([0u8; 32]),
+                "protocolVersion": 23,
+                "sequence": self.ledger,
+            })),
+            "getLedgerEntries" => self.ledger_entries(params),
+            "simulateTransaction" => self.simulate(params),
+            "sendTransaction" => self.send(params),
+            "getTransaction" => Ok(self.transaction(params["hash"].as_str().unwrap_or_default())),
+            "getTransactions" => Ok(self.transactions(params)),
+            _ => Err(rpc_error(-32601, &format!("method not found: {method}"))),
+        }
+    }
+
+    fn fund(&mut self, address: &str) -> Result<(), Error> {
+        let account_id = AccountId::from_str(address)?;
+        let key = LedgerKey::Account(LedgerKeyAccount {
+            account_id: account_id.clone(),
+        });
+        let data = LedgerEntryData::Account(AccountEntry {
+            account_id,
+            balance: 100_000_000_000,
+            seq_num: SequenceNumber(1),
+            num_sub_entries: 0,
+            inflation_dest: None,
+            flags: 0,
+            home_domain: Default::default(),
+            thresholds: Thresholds([1, 0, 0, 0]),
+            signers: Default::default(),
+            ext: AccountEntryExt::V0,
+        });
+        self.entries.retain(|(k, _)| *k != key);
+        self.entries.push((key, data));
+        Ok(())
+    }
+
+    fn ledger_entries(&self, params: &Value) -> Result<Value, Value> {
+        let keys = params["keys"].as_array().cloned().unwrap_or_default();
+        let mut entries = vec![];
+        for key in keys {
+            let key = LedgerKey::from_xdr_base64(key.as_str().unwrap_or_default(), Limits::none())
+                .map_err(|_| rpc_error(-32602, "invalid ledger key"))?;
+            if let Some((key, data)) = self.entries.iter().find(|(k, _)| *k == key) {
+                entries.push(json!({
+                    "key": to_base64(key),
+                    "xdr": to_base64(data),
+                    "lastModifiedLedgerSeq": self.ledger,
+                }));
+            }
+        }
+        Ok(json!({"entries": entries, "latestLedger": self.ledger}))
+    }
+
+    fn simulate(&self, params: &Value) -> Result<Value, Value> {
+        let envelope = parse_envelope(params)?;
+        let r = &self.resources;
+        let data = SorobanTransactionData {
+            ext: SorobanTransactionDataExt::V0,
+            resources: SorobanResources {
+                footprint: r.footprint.clone(),
+                instructions: r.cpu_insns as u32,
+                disk_read_bytes: r.read_bytes,
+                write_bytes: r.write_bytes,
+            },
+            resource_fee: r.non_refundable_fee + r.refundable_fee,
+        };
+        Ok(json!({
+            "latestLedger": self.ledger,
+            "minResourceFee": (r.non_refundable_fee + r.refundable_fee).to_string(),
+            "results": [{
+                "auth": [],
+                "xdr": to_base64(&self.return_value(&envelope)),
+            }],
+            "transactionData": to_base64(&data),
+        }))
+    }
+
+    fn send(&mut self, params: &Value) -> Result<Value, Value> {
+        let envelope = parse_envelope(params)?;
+        let hash = watcher::envelope_hash(&envelope, &self.passphrase)
+            .ok_or_else(|| rpc_error(-32602, "unsupported envelope"))?;
+        self.ledger += 1;
+        if self.outcome == MockOutcome::Success {
+            if let TransactionEnvelope::Tx(e) = &envelope {
+                for op in e.tx.operations.iter() {
+                    if let OperationBody::CreateAccount(op) = &op.body {
+                        let _ = self.fund(&op.destination.to_string());
+                    }
+                }
+            }
+        }
+        if self.outcome != MockOutcome::Dropped {
+            self.transactions.push(MockTransaction {
+                hash: hash.clone(),
+                envelope,
+                ledger: self.ledger,
+                failed: self.outcome == MockOutcome::Failed,
+            });
+        }
+        Ok(json!({
+            "status": "PENDING",
+            "hash": hash,
+            "latestLedger": self.ledger - 1,
+            "latestLedgerCloseTime": "0",
+        }))
+    }
+
+    fn transaction(&self, hash: &str) -> Value {
+        let mut res = json!({
+            "latestLedger": self.ledger,
+            "latestLedgerCloseTime": "0",
+            "oldestLedger": 1,
+            "oldestLedgerCloseTime": "0",
+        });
+        match self.transactions.iter().find(|tx| tx.hash == hash) {
+            Some(tx) => merge(&mut res, self.details(tx)),
+            None => merge(&mut res, json!({"status": "NOT_FOUND"})),
+        }
+        res["createdAt"] = json!("0");
+        res
+    }
+
+    // the cursor is the index of the next transaction
+    fn transactions(&self, params: &Value) -> Value {
+        let first = match params["pagination"]["cursor"].as_str() {
+            Some(cursor) => cursor.parse().unwrap_or(0),
+            None => {
+                let start = params["startLedger"].as_u64().unwrap_or(0) as u32;
+                self.transactions
+                    .iter()
+                    .position(|tx| tx.ledger >= start)
+                    .unwrap_or(self.transactions.len())
+            }
+        };
+        let limit = params["pagination"]["limit"].as_u64().unwrap_or(u64::MAX) as usize;
+        let page = self.transactions.iter().skip(first).take(limit);
+        let cursor = first + page.len();
+        let transactions: Vec<Value> = page
+            .map(|tx| {
+                let mut res = self.details(tx);
+                res["createdAt"] = json!(0);
+                res["txHash"] = json!(tx.hash);
+                res
+            })
+            .collect();
+        json!({
+            "latestLedger": self.ledger,
+            "latestLedgerCloseTimestamp": 0,
+            "oldestLedger": 1,
+            "oldestLedgerCloseTimestamp": 0,
+            "cursor": cursor.to_string(),
+            "transactions": transactions,
+        })
+    }
+
+    // an application with the changes, events, core metrics diagnostic
+    // events and fees from `resources`
+    fn details(&self, tx: &MockTransaction) -> Value {
+        let r = &self.resources;
+        let op_results = vec![OperationResult::OpInner(
+            OperationResultTr::InvokeHostFunction(match tx.failed {
+                true => InvokeHostFunctionResult::Trapped,
+                false => InvokeHostFunctionResult::Success(Default::default()),
+            }),
+        )]
+        .try_into()
+        .unwrap_or_default();
+        let result = TransactionResult {
+            fee_charged: r.fee_charged,
+            result: match tx.failed {
+                true => TransactionResultResult::TxFailed(op_results),
+                false => TransactionResultResult::TxSuccess(op_results),
+            },
+            ext: TransactionResultExt::V0,
+        };
+        let core_metrics = [("cpu_insn", r.cpu_insns), ("mem_byte", r.mem_bytes)]
+            .into_iter()
+            .map(|(key, value)| DiagnosticEvent {
+                in_successful_contract_call: true,
+                event: ContractEvent {
+                    ext: ExtensionPoint::V0,
+                    contract_id: None,
+                    type_: ContractEventType::Diagnostic,
+                    body: ContractEventBody::V0(ContractEventV0 {
+                        topics: vec![symbol("core_metrics"), symbol(key)]
+                            .try_into()
+                            .unwrap_or_default(),
+                        data: ScVal::U64(value),
+                    }),
+                },
+            })
+            .collect::<Vec<_>>();
+        let mut changes = vec![];
+        for data in &r.created {
+            let entry = ledger_entry(data.clone(), tx.ledger);
+            let key = entry.to_key();
+            changes.push(LedgerEntryChange::Created(entry));
+            if matches!(key, LedgerKey::ContractData(_) | LedgerKey::ContractCode(_)) {
+                let ttl = LedgerEntryData::Ttl(TtlEntry {
+                    key_hash: Hash(Sha256Hasher::hash(
+                        key.to_xdr(Limits::none()).unwrap_or_default(),
+                    )),
+                    live_until_ledger_seq: tx.ledger + ENTRY_TTL,
+                });
+                changes.push(LedgerEntryChange::Created(ledger_entry(ttl, tx.ledger)));
+            }
+        }
+        let meta = TransactionMeta::V4(TransactionMetaV4 {
+            ext: ExtensionPoint::V0,
+            tx_changes_before: Default::default(),
+            operations: vec![OperationMetaV2 {
+                ext: ExtensionPoint::V0,
+                changes: changes.try_into().unwrap_or_default(),
+                events: r.events.clone().try_into().unwrap_or_default(),
+            }]
+            .try_into()
+            .unwrap_or_default(),
+            tx_changes_after: Default::default(),
+            soroban_meta: Some(SorobanTransactionMetaV2 {
+                ext: SorobanTransactionMetaExt::V1(SorobanTransactionMetaExtV1 {
+                    ext: ExtensionPoint::V0,
+                    total_non_refundable_resource_fee_charged: r.non_refundable_fee,
+                    total_refundable_resource_fee_charged: r.refundable_fee,
+                    rent_fee_charged: r.rent_fee,
+                }),
+                return_value: Some(self.return_value(&tx.envelope)),
+            }),
+            events: Default::default(),
+            diagnostic_events: core_metrics.try_into().unwrap_or_default(),
+        });
+        json!({
+            "status": if tx.failed { "FAILED" } else { "SUCCESS" },
+            "ledger": tx.ledger,
+            "applicationOrder": 1,
+            "feeBump": matches!(tx.envelope, TransactionEnvelope::TxFeeBump(_)),
+            "envelopeXdr": to_base64(&tx.envelope),
+            "resultXdr": to_base64(&result),
+            "resultMetaXdr": to_base64(&meta),
+        })
+    }
+
+    // wasm hash of uploads, contract address of creations
+    fn return_value(&self, envelope: &TransactionEnvelope) -> ScVal {
+        let host_function = match envelope {
+            TransactionEnvelope::Tx(e) => e.tx.operations.first().map(|op| &op.body),
+            _ => None,
+        };
+        match host_function {
+            Some(OperationBody::InvokeHostFunction(op)) => match &op.host_function {
+                HostFunction::UploadContractWasm(wasm) => ScVal::Bytes(ScBytes(
+                    Sha256Hasher::hash(wasm)
+                        .to_vec()
+                        .try_into()
+                        .unwrap_or_default(),
+                )),
+                HostFunction::CreateContract(args) => {
+                    contract_address(&self.passphrase, &args.contract_id_preimage)
+                }
+                HostFunction::CreateContractV2(args) => {
+                    contract_address(&self.passphrase, &args.contract_id_preimage)
+                }
+                HostFunction::InvokeContract(_) => self.resources.return_value.clone(),
+            },
+            _ => ScVal::Void,
+        }
+    }
+}
+
+fn contract_address(passphrase: &str, preimage: &soroban_client::xdr::ContractIdPreimage) -> ScVal {
+    let id = statistics::contract_id(passphrase, preimage);
+    ScAddress::from_str(&id)
+        .map(ScVal::Address)
+        .unwrap_or(ScVal::Void)
+}
+
+fn parse_envelope(params: &Value) -> Result<TransactionEnvelope, Value> {
+    let xdr = params["transaction"].as_str().unwrap_or_default();
+    TransactionEnvelope::from_xdr_base64(xdr, Limits::none())
+        .map_err(|_| rpc_error(-32602, "invalid transaction envelope"))
+}
+
+fn rpc_error(code: i64, message: &str) -> Value {
+    json!({"code": code, "message": message})
+}
+
+fn symbol(s: &str) -> ScVal {
+    ScVal::Symbol(ScSymbol(s.try_into().unwrap_or_default()))
+}
+
+fn ledger_entry(data: LedgerEntryData, ledger: u32) -> LedgerEntry {
+    LedgerEntry {
+        last_modified_ledger_seq: ledger,
+        data,
+        ext: LedgerEntryExt::V0,
+    }
+}
+
+fn to_base64(value: &impl WriteXdr) -> String {
+    value.to_xdr_base64(Limits::none()).unwrap_or_default()
+}
+
+fn merge(target: &mut Value, other: Value) {
+    if let (Some(target), Value::Object(other)) = (target.as_object_mut(), other) {
+        target.extend(other);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crate::footprint::{EntryAccess, EntryChange, EntryKind};
+    use crate::StellarRpcServer;
+    use soroban_client::account::{Account, AccountBehavior};
+    use soroban_client::keypair::{Keypair, KeypairBehavior};
+    use soroban_client::operation::Operation;
+    use soroban_client::transaction::{
+        Transaction, TransactionBehavior, TransactionBuilder, TransactionBuilderBehavior,
+    };
+
+    pub(crate) const CONTRACT: [u8; 32] = [7; 32];
+
+    async fn server(mock: &MockRpc) -> StellarRpcServer {
+        let config = crate::NetworkConfig {
+            poll_interval: std::time::Duration::from_millis(10),
+            ..Default::default()
+        };
+        StellarRpcServer::with_config(&mock.url(), MockRpc::options(), config)
+            .await
+            .unwrap()
+    }
+
+    // transfer(5) on CONTRACT from `keypair` at sequence 2
+    pub(crate) fn transfer_tx(keypair: &Keypair) -> Transaction {
+        let mut account = Account::new(&keypair.public_key(), "1").unwrap();
+        let contract_id = stellar_strkey::Contract(CONTRACT).to_string();
+        let op = Operation::new()
+            .invoke_contract(&contract_id, "transfer", vec![ScVal::U32(5)], None)
+            .unwrap();
+        TransactionBuilder::new(&mut account, Network::Standalone.passphrase(), None)
+            .fee(1000u32)
+            .add_operation(op)
+            .build()
+    }
+
+    // the next simulation reports the storage of CONTRACT as archived
+    fn push_archived(mock: &MockRpc) {
+        let resources = MockResources::default().with_storage(ContractId(Hash(CONTRACT)));
+        let data = SorobanTransactionData {
+            ext: SorobanTransactionDataExt::V0,
+            resources: SorobanResources {
+                footprint: resources.footprint,
+                instructions: 0,
+                disk_read_bytes: 0,
+                write_bytes: 0,
+            },
+            resource_fee: 1_000,
+        };
+        mock.push_response(
+            "simulateTransaction",
+            json!({
+                "latestLedger": mock.latest_ledger(),
+                "minResourceFee": "6000",
+                "results": [{"auth": [], "xdr": to_base64(&ScVal::Void)}],
+                "transactionData": to_base64(&data),
+                "restorePreamble": {
+                    "minResourceFee": "1000",
+                    "transactionData": to_base64(&data),
+                },
+            }),
+        );
+    }
+
+    // invoke transfer(5) on CONTRACT and wait for its sample
+    async fn transfer(server: &mut StellarRpcServer) {
+        let keypair = Keypair::random().unwrap();
+        let tx = transfer_tx(&keypair);
+        let mut tx = server.prepare_transaction(&tx).await.unwrap();
+        tx.sign(&[keypair]);
+        server.send_transaction(tx).await.unwrap();
+        server.flush().await.unwrap();
+    }
+
+    #[test]
+    fn test_invoke() {
+        let mock = MockRpc::start().unwrap();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let mut server = server(&mock).await;
+            transfer(&mut server).await;
+
+            let contract_id = stellar_strkey::Contract(CONTRACT).to_string();
+            let samples = &server.store()[&contract_id]["transfer"];
+            assert_eq!(samples.len(), 1);
+            let sample = &samples[0];
+            let resources = MockResources::default();
+            assert_eq!(sample.cpu_insns, Some(resources.cpu_insns));
+            assert_eq!(sample.mem_bytes, Some(resources.mem_bytes));
+            assert_eq!(sample.read_bytes, Some(resources.read_bytes));
+            assert_eq!(sample.fee_charged, Some(resources.fee_charged));
+            assert_eq!(
+                sample.resource_fee,
+                Some(resources.non_refundable_fee + resources.refundable_fee)
+            );
+            assert_eq!(sample.rent_fee, Some(resources.rent_fee));
+            assert_eq!(sample.args, vec![ScVal::U32(5)]);
+            assert_eq!(sample.ledger, Some(mock.latest_ledger()));
+
+            server.print_table().await.unwrap();
+            assert!(server.store().is_empty());
+            let requests = mock.requests();
+            assert_eq!(requests[0], "getNetwork");
+            assert!(requests.contains(&"simulateTransaction".to_string()));
+            assert!(requests.contains(&"sendTransaction".to_string()));
+            assert!(requests.contains(&"getTransactions".to_string()));
+            assert!(requests.contains(&"getLedgerEntries".to_string()));
+        });
+    }
+
+    #[test]
+    fn test_invoke_storage() {
+        let mock = MockRpc::start().unwrap();
+        let resources = MockResources::default().with_storage(ContractId(Hash(CONTRACT)));
+        mock.set_resources(resources.clone());
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let mut server = server(&mock).await;
+            transfer(&mut server).await;
+
+            let contract_id = stellar_strkey::Contract(CONTRACT).to_string();
+            let sample = &server.store()[&contract_id]["transfer"][0];
+            let data_len = resources.created[0].to_xdr(Limits::none()).unwrap().len();
+            assert_eq!(sample.entry_bytes, Some(data_len));
+            assert_eq!(sample.entry_reads, Some(0));
+            assert_eq!(sample.entry_writes, Some(1));
+
+            assert_eq!(sample.footprint.len(), 1);
+            let entry = &sample.footprint[0];
+            assert_eq!(entry.key, resources.footprint.read_write[0]);
+            assert_eq!(entry.kind, EntryKind::PersistentData);
+            assert_eq!(entry.access, EntryAccess::ReadWrite);
+            assert_eq!(entry.owner.as_deref(), Some(contract_id.as_str()));
+            assert_eq!(entry.key_val, Some(symbol("balance")));
+            assert_eq!(entry.change, EntryChange::Created);
+            assert_eq!(entry.size_before, None);
+            assert_eq!(entry.size_after, Some(data_len));
+            assert_eq!(
+                entry.live_until_after,
+                Some(mock.latest_ledger() + ENTRY_TTL)
+            );
+
+            // the return value counts toward the events size
+            let event_len = resources.events[0].to_xdr(Limits::none()).unwrap().len();
+            let return_len = ScVal::Void.to_xdr(Limits::none()).unwrap().len();
+            assert_eq!(sample.events_count, Some(1));
+            assert_eq!(sample.events_bytes, Some(event_len + return_len));
+            assert_eq!(sample.event_topics, vec!["transfer".to_string()]);
+        });
+    }
+
+    #[test]
+    fn test_auto_restore() {
+        let mock = MockRpc::start().unwrap();
+        let keypair = Keypair::random().unwrap();
+        mock.fund(&keypair.public_key()).unwrap();
+        push_archived(&mock);
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let mut server = server(&mock).await;
+            server.set_auto_restore(keypair.clone());
+            let contract_id = stellar_strkey::Contract(CONTRACT).to_string();
+            let tx = transfer_tx(&keypair);
+            let mut tx = server.prepare_transaction(&tx).await.unwrap();
+            tx.sign(&[keypair]);
+            server.send_transaction(tx).await.unwrap();
+            server.flush().await.unwrap();
+
+            // the restore took sequence 2, the invocation was bumped to 3
+            let sent = mock.sent();
+            assert_eq!(sent.len(), 2);
+            let (TransactionEnvelope::Tx(restore), TransactionEnvelope::Tx(invoke)) =
+                (&sent[0], &sent[1])
+            else {
+                panic!("unexpected envelopes");
+            };
+            assert!(matches!(
+                restore.tx.operations[0].body,
+                OperationBody::RestoreFootprint(_)
+            ));
+            assert_eq!(restore.tx.seq_num, SequenceNumber(2));
+            assert!(matches!(
+                invoke.tx.operations[0].body,
+                OperationBody::InvokeHostFunction(_)
+            ));
+            assert_eq!(invoke.tx.seq_num, SequenceNumber(3));
+
+            let functions = &server.store()[&contract_id];
+            assert_eq!(functions["transfer:restore"].len(), 1);
+            assert_eq!(functions["transfer"].len(), 1);
+            assert_eq!(functions["transfer"][0].ledger, Some(mock.latest_ledger()));
+        });
+    }
+
+    #[test]
+    fn test_restore_required() {
+        let mock = MockRpc::start().unwrap();
+        push_archived(&mock);
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let mut server = server(&mock).await;
+            let tx = transfer_tx(&Keypair::random().unwrap());
+            let res = server.prepare_transaction(&tx).await;
+            assert!(matches!(res, Err(Error::RestoreRequired)), "{res:?}");
+            assert!(mock.sent().is_empty());
+        });
+    }
+}
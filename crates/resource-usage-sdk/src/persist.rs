@@ -0,0 +1,445 @@
+use rusqlite::{params_from_iter, types::Value, Connection, Row};
+use soroban_client::xdr::{Limits, ReadXdr, ScVal, WriteXdr};
+use std::collections::HashSet;
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::rpc_server::ContractStore;
+use crate::show;
+use crate::statistics::ResourceMetric;
+use crate::trend::{self, FunctionTrend};
+use crate::{Error, Network};
+
+// stored as the sqlite user_version, bump it with every schema change
+// migrate cannot infer from the columns and add its step there
+const SCHEMA_VERSION: i64 = 2;
+
+// context stored with every sample of a run
+#[derive(Debug, Clone)]
+pub struct RunContext {
+    pub run_id: String,
+    pub git_commit: Option<String>,
+    // unix seconds
+    pub timestamp: u64,
+    // short network name, see Network::name
+    pub network: String,
+}
+
+impl RunContext {
+    // new run id, git commit of the current directory if any
+    pub fn new(network: &str) -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Self {
+            run_id: format!("{}-{}", now.as_millis(), std::process::id()),
+            git_commit: git_commit(),
+            timestamp: now.as_secs(),
+            network: network.to_string(),
+        }
+    }
+}
+
+fn git_commit() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let commit = String::from_utf8(output.stdout).ok()?;
+    Some(commit.trim().to_string())
+}
+
+#[derive(Debug, Clone)]
+pub struct StoredSample {
+    pub run_id: String,
+    pub git_commit: Option<String>,
+    pub timestamp: u64,
+    pub network: String,
+    pub contract: String,
+    pub function: String,
+    // footprint, auth and diagnostic events are not persisted
+    pub metric: ResourceMetric,
+}
+
+#[derive(Debug, Clone)]
+pub struct RunInfo {
+    pub run_id: String,
+    pub git_commit: Option<String>,
+    pub timestamp: u64,
+    pub network: String,
+    pub samples: usize,
+}
+
+// every field is optional, samples match when all set fields are equal
+#[derive(Debug, Clone, Default)]
+pub struct SampleFilter {
+    pub run_id: Option<String>,
+    pub network: Option<String>,
+    pub contract: Option<String>,
+    pub function: Option<String>,
+    // unix seconds
+    pub since: Option<u64>,
+}
+
+#[derive(Debug)]
+pub struct MetricDb {
+    conn: Connection,
+}
+
+impl MetricDb {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, Error> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, Error> {
+        let metrics: Vec<String> = show::METRIC_KEYS
+            .iter()
+            .map(|key| format!("{key} INTEGER"))
+            .collect();
+        conn.execute_batch(&format!(
+            "CREATE TABLE IF NOT EXISTS samples (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                run_id TEXT NOT NULL,
+                git_commit TEXT,
+                timestamp INTEGER NOT NULL,
+                network TEXT NOT NULL,
+                ledger INTEGER,
+                tx_hash TEXT,
+                contract TEXT NOT NULL,
+                function TEXT NOT NULL,
+                {},
+                args TEXT NOT NULL,
+                event_topics TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS samples_function ON samples (contract, function);
+            CREATE INDEX IF NOT EXISTS samples_run ON samples (run_id);",
+            metrics.join(",\n")
+        ))?;
+        migrate(&conn)?;
+        Ok(Self { conn })
+    }
+
+    // append every sample of `store`, returns the number of rows
+    pub fn insert_store(
+        &mut self,
+        ctx: &RunContext,
+        store: &ContractStore,
+    ) -> Result<usize, Error> {
+        let columns = [
+            "run_id",
+            "git_commit",
+            "timestamp",
+            "network",
+            "ledger",
+            "tx_hash",
+            "contract",
+            "function",
+        ]
+        .iter()
+        .chain(show::METRIC_KEYS.iter())
+        .chain(["args", "event_topics"].iter())
+        .copied()
+        .collect::<Vec<_>>();
+        let placeholders = vec!["?"; columns.len()].join(", ");
+        let sql = format!(
+            "INSERT INTO samples ({}) VALUES ({placeholders})",
+            columns.join(", ")
+        );
+
+        let tx = self.conn.transaction()?;
+        let mut count = 0;
+        {
+            let mut stmt = tx.prepare(&sql)?;
+            for (contract, funcs) in store {
+                for (function, samples) in funcs {
+                    for sample in samples {
+                        let mut values: Vec<Value> = vec![
+                            ctx.run_id.clone().into(),
+                            ctx.git_commit.clone().into(),
+                            (ctx.timestamp as i64).into(),
+                            ctx.network.clone().into(),
+                            sample.ledger.map(|l| l as i64).into(),
+                            sample.tx_hash.clone().into(),
+                            contract.clone().into(),
+                            function.clone().into(),
+                        ];
+                        for key in show::METRIC_KEYS {
+                            values.push(show::get_metric_u64(sample, key).map(|v| v as i64).into());
+                        }
+                        values.push(args_to_json(&sample.args)?.into());
+                        values.push(serde_json::to_string(&sample.event_topics)?.into());
+                        stmt.execute(params_from_iter(values))?;
+                        count += 1;
+                    }
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(count)
+    }
+
+    // samples in insertion order
+    pub fn load_samples(&self, filter: &SampleFilter) -> Result<Vec<StoredSample>, Error> {
+        let mut conditions = vec![];
+        let mut values: Vec<Value> = vec![];
+        let text_filters = [
+            ("run_id", &filter.run_id),
+            ("network", &filter.network),
+            ("contract", &filter.contract),
+            ("function", &filter.function),
+        ];
+        for (column, value) in text_filters {
+            if let Some(value) = value {
+                conditions.push(format!("{column} = ?"));
+                values.push(value.clone().into());
+            }
+        }
+        if let Some(since) = filter.since {
+            conditions.push("timestamp >= ?".to_string());
+            values.push((since as i64).into());
+        }
+        let mut sql = format!(
+            "SELECT run_id, git_commit, timestamp, network, ledger, tx_hash, contract, function, {}, args, event_topics FROM samples",
+            show::METRIC_KEYS.join(", ")
+        );
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push_str(" ORDER BY id");
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(values), |row| Ok(read_row(row)))?;
+        let mut res = vec![];
+        for row in rows {
+            res.push(row??);
+        }
+        Ok(res)
+    }
+
+    // historical samples grouped like the live store, for reporting
+    pub fn load_store(&self, filter: &SampleFilter) -> Result<ContractStore, Error> {
+        let mut store = ContractStore::new();
+        for sample in self.load_samples(filter)? {
+            store
+                .entry(sample.contract)
+                .or_default()
+                .entry(sample.function)
+                .or_default()
+                .push(sample.metric);
+        }
+        Ok(store)
+    }
+
+    // samples of each run matching `filter`, from the oldest run
+    pub fn load_runs(&self, filter: &SampleFilter) -> Result<Vec<(RunInfo, ContractStore)>, Error> {
+        let mut res = vec![];
+        for run in self.runs()? {
+            if filter.run_id.as_ref().is_some_and(|id| *id != run.run_id) {
+                continue;
+            }
+            let filter = SampleFilter {
+                run_id: Some(run.run_id.clone()),
+                ..filter.clone()
+            };
+            let store = self.load_store(&filter)?;
+            if !store.is_empty() {
+                res.push((run, store));
+            }
+        }
+        Ok(res)
+    }
+
+    // avg and max of every metric across the stored runs, labelled by
+    // short commit
+    pub fn trend_report(&self, filter: &SampleFilter) -> Result<Vec<FunctionTrend>, Error> {
+        let runs: Vec<(String, ContractStore)> = self
+            .load_runs(filter)?
+            .into_iter()
+            .map(|(run, store)| {
+                let label = match &run.git_commit {
+                    Some(commit) => commit.chars().take(7).collect(),
+                    None => run.run_id,
+                };
+                (label, store)
+            })
+            .collect();
+        Ok(trend::trends(&runs))
+    }
+
+    // runs from the oldest to the newest
+    pub fn runs(&self) -> Result<Vec<RunInfo>, Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT run_id, git_commit, MIN(timestamp), network, COUNT(*)
+             FROM samples GROUP BY run_id ORDER BY MIN(id)",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(RunInfo {
+                run_id: row.get(0)?,
+                git_commit: row.get(1)?,
+                timestamp: row.get::<_, i64>(2)? as u64,
+                network: row.get(3)?,
+                samples: row.get::<_, i64>(4)? as usize,
+            })
+        })?;
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+}
+
+// databases created before a metric was added lack its column, other
+// changes are applied from the stored user_version
+fn migrate(conn: &Connection) -> Result<(), Error> {
+    let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version > SCHEMA_VERSION {
+        return Err(Error::UnsupportedSchema(version));
+    }
+    let tx = conn.unchecked_transaction()?;
+    let columns = tx
+        .prepare("PRAGMA table_info(samples)")?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<HashSet<_>, _>>()?;
+    for key in show::METRIC_KEYS {
+        if !columns.contains(key) {
+            tx.execute_batch(&format!("ALTER TABLE samples ADD COLUMN {key} INTEGER"))?;
+        }
+    }
+    // version 1 stored the network passphrase
+    if version < 2 {
+        for network in [
+            Network::Standalone,
+            Network::Testnet,
+            Network::Futurenet,
+            Network::Mainnet,
+        ] {
+            tx.execute(
+                "UPDATE samples SET network = ? WHERE network = ?",
+                [network.name(), network.passphrase()],
+            )?;
+        }
+    }
+    tx.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    tx.commit()?;
+    Ok(())
+}
+
+fn read_row(row: &Row) -> Result<StoredSample, Error> {
+    let mut metric = ResourceMetric {
+        ledger: row.get::<_, Option<i64>>(4)?.map(|l| l as u32),
+        tx_hash: row.get(5)?,
+        ..Default::default()
+    };
+    let offset = 8;
+    for (i, key) in show::METRIC_KEYS.iter().enumerate() {
+        if let Some(value) = row.get::<_, Option<i64>>(offset + i)? {
+            show::set_metric_u64(&mut metric, key, value as u64);
+        }
+    }
+    let args: String = row.get(offset + show::METRIC_KEYS.len())?;
+    metric.args = args_from_json(&args)?;
+    let topics: String = row.get(offset + show::METRIC_KEYS.len() + 1)?;
+    metric.event_topics = serde_json::from_str(&topics)?;
+    Ok(StoredSample {
+        run_id: row.get(0)?,
+        git_commit: row.get(1)?,
+        timestamp: row.get::<_, i64>(2)? as u64,
+        network: row.get(3)?,
+        contract: row.get(6)?,
+        function: row.get(7)?,
+        metric,
+    })
+}
+
+// json array of base64 xdr values
+fn args_to_json(args: &[ScVal]) -> Result<String, Error> {
+    let args = args
+        .iter()
+        .map(|arg| arg.to_xdr_base64(Limits::none()))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(serde_json::to_string(&args)?)
+}
+
+fn args_from_json(json: &str) -> Result<Vec<ScVal>, Error> {
+    let args: Vec<String> = serde_json::from_str(json)?;
+    Ok(args
+        .iter()
+        .map(|arg| ScVal::from_xdr_base64(arg, Limits::none()))
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_migrate() {
+        // samples table of a database created before most metrics existed
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE samples (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                run_id TEXT NOT NULL,
+                git_commit TEXT,
+                timestamp INTEGER NOT NULL,
+                network TEXT NOT NULL,
+                ledger INTEGER,
+                tx_hash TEXT,
+                contract TEXT NOT NULL,
+                function TEXT NOT NULL,
+                cpu_insns INTEGER,
+                args TEXT NOT NULL,
+                event_topics TEXT NOT NULL
+            );
+            INSERT INTO samples (run_id, timestamp, network, contract, function, cpu_insns, args, event_topics)
+            VALUES ('old', 1, 'Test SDF Network ; September 2015', 'C1', 'transfer', 10, '[]', '[]');
+            PRAGMA user_version = 1;",
+        )
+        .unwrap();
+
+        let mut db = MetricDb::init(conn).unwrap();
+        let version: i64 = db
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+
+        let metric = ResourceMetric {
+            cpu_insns: Some(20),
+            rent_fee: Some(5),
+            ..Default::default()
+        };
+        let store = ContractStore::from([(
+            "C1".to_string(),
+            [("transfer".to_string(), vec![metric])].into(),
+        )]);
+        let mut ctx = RunContext::new(Network::Testnet.name());
+        ctx.run_id = "new".into();
+        assert_eq!(db.insert_store(&ctx, &store).unwrap(), 1);
+
+        let samples = db.load_samples(&SampleFilter::default()).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].metric.cpu_insns, Some(10));
+        assert_eq!(samples[0].metric.rent_fee, None);
+        assert_eq!(samples[1].metric.cpu_insns, Some(20));
+        assert_eq!(samples[1].metric.rent_fee, Some(5));
+        // passphrases of version 1 rows replaced by the network name
+        assert!(samples.iter().all(|s| s.network == "testnet"));
+
+        // reopening an up to date database is a no-op
+        let db = MetricDb::init(db.conn).unwrap();
+        db.conn
+            .pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+        assert!(matches!(
+            MetricDb::init(db.conn),
+            Err(Error::UnsupportedSchema(_))
+        ));
+    }
+}
@@ -0,0 +1,333 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::{show, statistics::ResourceMetric, Error};
+
+// histogram buckets as fractions of the network limit
+const LIMIT_BUCKETS: [f64; 8] = [0.1, 0.25, 0.5, 0.75, 0.8, 0.9, 0.95, 1.0];
+// a client that never sends its request must not hold the exporter forever
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+// pause of the accept loop when no connection is waiting
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(50);
+// buckets of metrics without a network limit
+const DEFAULT_BUCKETS: [f64; 8] = [1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8];
+
+#[derive(Debug, Clone, Default)]
+struct Series {
+    buckets: Vec<(f64, u64)>,
+    sum: f64,
+    count: u64,
+    last: u64,
+    max: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct MetricsRegistry {
+    network: String,
+    limits: BTreeMap<&'static str, u64>,
+    // (contract, function, metric)
+    series: BTreeMap<(String, String, &'static str), Series>,
+    // (contract, function) -> confirmed transactions
+    transactions: BTreeMap<(String, String), u64>,
+}
+
+impl MetricsRegistry {
+    // `limits` are the per-transaction network limits, e.g.
+    // StellarRpcServer::get_tx_limits
+    pub fn new(network: &str, limits: &HashMap<&'static str, u64>) -> Self {
+        Self {
+            network: network.to_string(),
+            limits: limits.iter().map(|(k, v)| (*k, *v)).collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn observe(&mut self, contract: &str, function: &str, sample: &ResourceMetric) {
+        *self
+            .transactions
+            .entry((contract.to_string(), function.to_string()))
+            .or_default() += 1;
+        for key in show::METRIC_KEYS {
+            let Some(value) = show::get_metric_u64(sample, key) else {
+                continue;
+            };
+            let bounds: Vec<f64> = match self.limits.get(key) {
+                Some(limit) => LIMIT_BUCKETS.iter().map(|f| f * *limit as f64).collect(),
+                None => DEFAULT_BUCKETS.to_vec(),
+            };
+            let series = self
+                .series
+                .entry((contract.to_string(), function.to_string(), key))
+                .or_insert_with(|| Series {
+                    buckets: bounds.iter().map(|b| (*b, 0)).collect(),
+                    ..Default::default()
+                });
+            for (bound, count) in series.buckets.iter_mut() {
+                if value as f64 <= *bound {
+                    *count += 1;
+                }
+            }
+            series.sum += value as f64;
+            series.count += 1;
+            series.last = value;
+            series.max = series.max.max(value);
+        }
+    }
+
+    // prometheus text exposition format
+    pub fn render(&self) -> String {
+        let network = escape(&self.network);
+        let mut out = String::new();
+
+        out.push_str("# HELP soroban_resource_usage Resource usage of confirmed transactions.\n");
+        out.push_str("# TYPE soroban_resource_usage histogram\n");
+        for ((contract, function, metric), s) in &self.series {
+            let labels = format!(
+                "contract=\"{}\",function=\"{}\",metric=\"{metric}\",network=\"{network}\"",
+                escape(contract),
+                escape(function)
+            );
+            for (bound, count) in &s.buckets {
+                let _ = writeln!(
+                    out,
+                    "soroban_resource_usage_bucket{{{labels},le=\"{bound}\"}} {count}"
+                );
+            }
+            let _ = writeln!(
+                out,
+                "soroban_resource_usage_bucket{{{labels},le=\"+Inf\"}} {}",
+                s.count
+            );
+            let _ = writeln!(out, "soroban_resource_usage_sum{{{labels}}} {}", s.sum);
+            let _ = writeln!(out, "soroban_resource_usage_count{{{labels}}} {}", s.count);
+        }
+
+        self.write_gauge(
+            &mut out,
+            "soroban_resource_last",
+            "Resource usage of the last confirmed transaction.",
+            |s| s.last,
+        );
+        self.write_gauge(
+            &mut out,
+            "soroban_resource_max",
+            "Highest resource usage seen.",
+            |s| s.max,
+        );
+
+        out.push_str("# HELP soroban_resource_limit Network limit of a resource.\n");
+        out.push_str("# TYPE soroban_resource_limit gauge\n");
+        for (metric, limit) in &self.limits {
+            let _ = writeln!(
+                out,
+                "soroban_resource_limit{{metric=\"{metric}\",network=\"{network}\"}} {limit}"
+            );
+        }
+
+        out.push_str("# HELP soroban_transactions_total Confirmed transactions.\n");
+        out.push_str("# TYPE soroban_transactions_total counter\n");
+        for ((contract, function), count) in &self.transactions {
+            let _ = writeln!(
+                out,
+                "soroban_transactions_total{{contract=\"{}\",function=\"{}\",network=\"{network}\"}} {count}",
+                escape(contract),
+                escape(function)
+            );
+        }
+        out
+    }
+
+    fn write_gauge(&self, out: &mut String, name: &str, help: &str, value: fn(&Series) -> u64) {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} gauge");
+        for ((contract, function, metric), s) in &self.series {
+            let _ = writeln!(
+                out,
+                "{name}{{contract=\"{}\",function=\"{}\",metric=\"{metric}\",network=\"{}\"}} {}",
+                escape(contract),
+                escape(function),
+                escape(&self.network),
+                value(s)
+            );
+        }
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+// stops the accept loop and releases the port when the last exporter clone
+// is dropped
+#[derive(Debug)]
+struct Listener {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// serves the registry on every http path of `addr`, one connection at a
+// time from a single thread
+#[derive(Debug, Clone)]
+pub struct PrometheusExporter {
+    registry: Arc<Mutex<MetricsRegistry>>,
+    addr: SocketAddr,
+    _listener: Arc<Listener>,
+}
+
+impl PrometheusExporter {
+    // `network` is the label of every series, e.g. Network::name
+    pub fn serve(
+        addr: &str,
+        network: &str,
+        limits: &HashMap<&'static str, u64>,
+    ) -> Result<Self, Error> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        // polled so the loop sees the stop flag
+        listener.set_nonblocking(true)?;
+        let registry = Arc::new(Mutex::new(MetricsRegistry::new(network, limits)));
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let registry = registry.clone();
+            let stop = stop.clone();
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    match listener.accept() {
+                        Ok((stream, _)) => {
+                            let _ = respond(stream, &registry);
+                        }
+                        // WouldBlock when no client is waiting
+                        Err(_) => thread::sleep(ACCEPT_INTERVAL),
+                    }
+                }
+            })
+        };
+        Ok(Self {
+            registry,
+            addr,
+            _listener: Arc::new(Listener {
+                stop,
+                thread: Some(thread),
+            }),
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn observe(&self, contract: &str, function: &str, sample: &ResourceMetric) {
+        if let Ok(mut registry) = self.registry.lock() {
+            registry.observe(contract, function, sample);
+        }
+    }
+
+    pub fn render(&self) -> String {
+        self.registry.lock().map(|r| r.render()).unwrap_or_default()
+    }
+}
+
+fn respond(mut stream: TcpStream, registry: &Mutex<MetricsRegistry>) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    // the request itself is ignored, every path returns the metrics
+    let mut buf = [0u8; 1024];
+    let _ = stream.read(&mut buf)?;
+    let body = registry.lock().map(|r| r.render()).unwrap_or_default();
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample(cpu_insns: u64) -> ResourceMetric {
+        ResourceMetric {
+            cpu_insns: Some(cpu_insns),
+            mem_bytes: Some(50),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let limits = HashMap::from([("cpu_insns", 10_000)]);
+        let mut registry = MetricsRegistry::new("testnet", &limits);
+        registry.observe("C1", "transfer", &sample(1_000));
+        registry.observe("C1", "transfer", &sample(3_000));
+        let out = registry.render();
+
+        let labels = r#"contract="C1",function="transfer",metric="cpu_insns",network="testnet""#;
+        let mem_labels =
+            r#"contract="C1",function="transfer",metric="mem_bytes",network="testnet""#;
+        for line in [
+            "# HELP soroban_resource_usage Resource usage of confirmed transactions.".to_string(),
+            "# TYPE soroban_resource_usage histogram".to_string(),
+            // buckets are fractions of the cpu limit
+            format!(r#"soroban_resource_usage_bucket{{{labels},le="1000"}} 1"#),
+            format!(r#"soroban_resource_usage_bucket{{{labels},le="2500"}} 1"#),
+            format!(r#"soroban_resource_usage_bucket{{{labels},le="5000"}} 2"#),
+            format!(r#"soroban_resource_usage_bucket{{{labels},le="+Inf"}} 2"#),
+            format!("soroban_resource_usage_sum{{{labels}}} 4000"),
+            format!("soroban_resource_usage_count{{{labels}}} 2"),
+            // mem_bytes has no limit and uses the default buckets
+            format!(r#"soroban_resource_usage_bucket{{{mem_labels},le="10"}} 0"#),
+            format!(r#"soroban_resource_usage_bucket{{{mem_labels},le="100"}} 2"#),
+            "# TYPE soroban_resource_last gauge".to_string(),
+            format!("soroban_resource_last{{{labels}}} 3000"),
+            format!("soroban_resource_max{{{labels}}} 3000"),
+            "# TYPE soroban_resource_limit gauge".to_string(),
+            r#"soroban_resource_limit{metric="cpu_insns",network="testnet"} 10000"#.to_string(),
+            "# TYPE soroban_transactions_total counter".to_string(),
+            r#"soroban_transactions_total{contract="C1",function="transfer",network="testnet"} 2"#
+                .to_string(),
+        ] {
+            assert!(out.lines().any(|l| l == line), "missing {line} in\n{out}");
+        }
+    }
+
+    #[test]
+    fn test_serve() {
+        let limits = HashMap::from([("cpu_insns", 10_000)]);
+        let exporter = PrometheusExporter::serve("127.0.0.1:0", "testnet", &limits).unwrap();
+        exporter.observe("C1", "transfer", &sample(1_000));
+        let addr = exporter.addr();
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"GET /metrics HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("soroban_transactions_total"));
+
+        // the last clone releases the port
+        let clone = exporter.clone();
+        drop(exporter);
+        assert!(TcpListener::bind(addr).is_err());
+        drop(clone);
+        TcpListener::bind(addr).unwrap();
+    }
+}
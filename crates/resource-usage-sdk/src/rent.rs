@@ -0,0 +1,214 @@
+use soroban_client::xdr::{ConfigSettingEntry, ConfigSettingId};
+
+// 1kb data increment used by the fee config
+pub(crate) const DATA_SIZE_1KB_INCREMENT: i64 = 1024;
+// lower bound applied by the host to the rent fee per 1kb
+const MINIMUM_RENT_WRITE_FEE_PER_1KB: i64 = 1000;
+// about 30 days of 5s ledgers
+pub const DEFAULT_RENT_LEDGERS: u32 = 518_400;
+
+// config settings needed to estimate rent
+pub const RENT_CONFIG_SETTINGS: [ConfigSettingId; 4] = [
+    ConfigSettingId::ContractLedgerCostV0,
+    ConfigSettingId::ContractLedgerCostExtV0,
+    ConfigSettingId::StateArchival,
+    ConfigSettingId::LiveSorobanStateSizeWindow,
+];
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RentConfig {
+    pub fee_per_rent_1kb: i64,
+    pub fee_per_write_entry: i64,
+    pub fee_per_write_1kb: i64,
+    pub persistent_rent_rate_denominator: i64,
+    pub temporary_rent_rate_denominator: i64,
+    pub min_persistent_ttl: u32,
+    pub min_temporary_ttl: u32,
+    pub max_entry_ttl: u32,
+}
+
+impl RentConfig {
+    // build from the network config settings, None if one is missing
+    pub fn from_settings(settings: &[ConfigSettingEntry]) -> Option<Self> {
+        let mut cost = None;
+        let mut cost_ext = None;
+        let mut archival = None;
+        let mut window = None;
+        for setting in settings {
+            match setting {
+                ConfigSettingEntry::ContractLedgerCostV0(v) => cost = Some(v),
+                ConfigSettingEntry::ContractLedgerCostExtV0(v) => cost_ext = Some(v),
+                ConfigSettingEntry::StateArchival(v) => archival = Some(v),
+                ConfigSettingEntry::LiveSorobanStateSizeWindow(v) => window = Some(v),
+                _ => {}
+            }
+        }
+        let (cost, cost_ext, archival, window) = (cost?, cost_ext?, archival?, window?);
+
+        let state_size = if window.is_empty() {
+            0
+        } else {
+            (window.iter().map(|v| *v as u128).sum::<u128>() / window.len() as u128) as i64
+        };
+
+        let fee_rate_multiplier = cost
+            .rent_fee1_kb_soroban_state_size_high
+            .saturating_sub(cost.rent_fee1_kb_soroban_state_size_low);
+        let target = cost.soroban_state_target_size_bytes.max(1);
+        let fee_per_rent_1kb = if state_size < cost.soroban_state_target_size_bytes {
+            div_ceil(fee_rate_multiplier.saturating_mul(state_size), target)
+                .saturating_add(cost.rent_fee1_kb_soroban_state_size_low)
+        } else {
+            let over_target = state_size.saturating_sub(cost.soroban_state_target_size_bytes);
+            let post_target_fee = div_ceil(
+                fee_rate_multiplier
+                    .saturating_mul(over_target)
+                    .saturating_mul(cost.soroban_state_rent_fee_growth_factor as i64),
+                target,
+            );
+            cost.rent_fee1_kb_soroban_state_size_high
+                .saturating_add(post_target_fee)
+        };
+
+        Some(RentConfig {
+            fee_per_rent_1kb: fee_per_rent_1kb.max(MINIMUM_RENT_WRITE_FEE_PER_1KB),
+            fee_per_write_entry: cost.fee_write_ledger_entry,
+            fee_per_write_1kb: cost_ext.fee_write1_kb,
+            persistent_rent_rate_denominator: archival.persistent_rent_rate_denominator,
+            temporary_rent_rate_denominator: archival.temp_rent_rate_denominator,
+            min_persistent_ttl: archival.min_persistent_ttl,
+            min_temporary_ttl: archival.min_temporary_ttl,
+            max_entry_ttl: archival.max_entry_ttl,
+        })
+    }
+
+    // rent fee (stroops) to keep `size` bytes alive for `ledgers` ledgers
+    pub fn rent_fee(&self, is_persistent: bool, size: u32, ledgers: u32) -> i64 {
+        let num = (size as i64)
+            .saturating_mul(self.fee_per_rent_1kb)
+            .saturating_mul(ledgers as i64);
+        let rate = if is_persistent {
+            self.persistent_rent_rate_denominator
+        } else {
+            self.temporary_rent_rate_denominator
+        };
+        div_ceil(num, DATA_SIZE_1KB_INCREMENT.saturating_mul(rate).max(1))
+    }
+
+    // rent fee plus the ttl entry write needed to extend one entry
+    pub fn extend_fee(&self, is_persistent: bool, size: u32, ledgers: u32) -> i64 {
+        // ttl entry: 32 bytes key hash + 4 bytes live_until + xdr overhead
+        const TTL_ENTRY_SIZE: i64 = 48;
+        self.rent_fee(is_persistent, size, ledgers)
+            .saturating_add(self.fee_per_write_entry)
+            .saturating_add(div_ceil(
+                TTL_ENTRY_SIZE.saturating_mul(self.fee_per_write_1kb),
+                DATA_SIZE_1KB_INCREMENT,
+            ))
+    }
+}
+
+pub(crate) fn div_ceil(num: i64, denom: i64) -> i64 {
+    let res = num / denom;
+    if num % denom != 0 {
+        res + 1
+    } else {
+        res
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_client::xdr::{
+        ConfigSettingContractLedgerCostExtV0, ConfigSettingContractLedgerCostV0,
+        StateArchivalSettings,
+    };
+
+    // rent fee per 1kb from `low` (empty state) to `high` (1mb target state)
+    fn settings(low: i64, high: i64, window: Vec<u64>) -> Vec<ConfigSettingEntry> {
+        vec![
+            ConfigSettingEntry::ContractLedgerCostV0(ConfigSettingContractLedgerCostV0 {
+                ledger_max_disk_read_entries: 0,
+                ledger_max_disk_read_bytes: 0,
+                ledger_max_write_ledger_entries: 0,
+                ledger_max_write_bytes: 0,
+                tx_max_disk_read_entries: 0,
+                tx_max_disk_read_bytes: 0,
+                tx_max_write_ledger_entries: 0,
+                tx_max_write_bytes: 0,
+                fee_disk_read_ledger_entry: 0,
+                fee_write_ledger_entry: 50,
+                fee_disk_read1_kb: 0,
+                soroban_state_target_size_bytes: 1_000_000,
+                rent_fee1_kb_soroban_state_size_low: low,
+                rent_fee1_kb_soroban_state_size_high: high,
+                soroban_state_rent_fee_growth_factor: 5,
+            }),
+            ConfigSettingEntry::ContractLedgerCostExtV0(ConfigSettingContractLedgerCostExtV0 {
+                tx_max_footprint_entries: 0,
+                fee_write1_kb: 3500,
+            }),
+            ConfigSettingEntry::StateArchival(StateArchivalSettings {
+                max_entry_ttl: 3_110_400,
+                min_temporary_ttl: 16,
+                min_persistent_ttl: 2_073_600,
+                persistent_rent_rate_denominator: 2103,
+                temp_rent_rate_denominator: 4206,
+                max_entries_to_archive: 0,
+                live_soroban_state_size_window_sample_size: 0,
+                live_soroban_state_size_window_sample_period: 0,
+                eviction_scan_size: 0,
+                starting_eviction_scan_level: 0,
+            }),
+            ConfigSettingEntry::LiveSorobanStateSizeWindow(window.try_into().unwrap()),
+        ]
+    }
+
+    #[test]
+    fn test_from_settings() {
+        // average state of 500kb, half way to the target
+        let config = RentConfig::from_settings(&settings(2000, 10000, vec![250_000, 750_000]));
+        let config = config.unwrap();
+        assert_eq!(config.fee_per_rent_1kb, 6000);
+        assert_eq!(config.fee_per_write_entry, 50);
+        assert_eq!(config.fee_per_write_1kb, 3500);
+        assert_eq!(config.persistent_rent_rate_denominator, 2103);
+        assert_eq!(config.temporary_rent_rate_denominator, 4206);
+        assert_eq!(config.min_persistent_ttl, 2_073_600);
+        assert_eq!(config.max_entry_ttl, 3_110_400);
+
+        // 500kb over the target grows 5 times faster
+        let config = RentConfig::from_settings(&settings(2000, 10000, vec![1_500_000]));
+        assert_eq!(config.unwrap().fee_per_rent_1kb, 30000);
+
+        let config = RentConfig::from_settings(&settings(2000, 10000, vec![]));
+        assert_eq!(config.unwrap().fee_per_rent_1kb, 2000);
+
+        // never below the host minimum
+        let config = RentConfig::from_settings(&settings(100, 500, vec![0]));
+        assert_eq!(config.unwrap().fee_per_rent_1kb, 1000);
+
+        let mut missing = settings(2000, 10000, vec![]);
+        missing.pop();
+        assert!(RentConfig::from_settings(&missing).is_none());
+    }
+
+    #[test]
+    fn test_rent_fee() {
+        let config = RentConfig::from_settings(&settings(2000, 10000, vec![500_000])).unwrap();
+        assert_eq!(config.rent_fee(true, 1024, 2103), 6000);
+        assert_eq!(config.rent_fee(false, 1024, 2103), 3000);
+        // rounded up
+        assert_eq!(config.rent_fee(true, 100, 1), 1);
+        assert_eq!(config.rent_fee(true, 0, 2103), 0);
+    }
+
+    #[test]
+    fn test_extend_fee() {
+        let config = RentConfig::from_settings(&settings(2000, 10000, vec![500_000])).unwrap();
+        // 48 bytes ttl entry write: ceil(48 * 3500 / 1024) = 165
+        assert_eq!(config.extend_fee(true, 1024, 2103), 6000 + 50 + 165);
+        assert_eq!(config.extend_fee(false, 0, 0), 50 + 165);
+    }
+}
@@ -1,5 +1,7 @@
 use soroban_client::account::Account;
 use soroban_client::error::Error;
+use soroban_client::keypair::{Keypair, KeypairBehavior};
+use soroban_client::operation::Operation;
 use soroban_client::soroban_rpc::GetEventsResponse;
 use soroban_client::soroban_rpc::GetFeeStatsResponse;
 use soroban_client::soroban_rpc::GetHealthResponse;
@@ -16,8 +18,22 @@ use soroban_client::soroban_rpc::SimulateTransactionResponse;
 use soroban_client::soroban_rpc::TransactionStatus;
 use soroban_client::transaction;
 use soroban_client::transaction::Transaction;
+use soroban_client::transaction::TransactionBehavior;
+use soroban_client::transaction::TransactionBuilder;
+use soroban_client::transaction::TransactionBuilderBehavior;
+use soroban_client::xdr::ConfigSettingEntry;
+use soroban_client::xdr::ConfigSettingId;
+use soroban_client::xdr::ContractDataDurability;
+use soroban_client::xdr::ContractExecutable;
+use soroban_client::xdr::LedgerEntryData;
 use soroban_client::xdr::LedgerKey;
+use soroban_client::xdr::LedgerKeyConfigSetting;
+use soroban_client::xdr::LedgerKeyContractCode;
+use soroban_client::xdr::LedgerKeyContractData;
+use soroban_client::xdr::ScAddress;
+use soroban_client::xdr::ScSpecEntry;
 use soroban_client::xdr::ScVal;
+use soroban_client::xdr::TransactionMeta;
 use soroban_client::Durability;
 use soroban_client::EventFilter;
 use soroban_client::Options;
@@ -25,19 +41,48 @@ use soroban_client::Pagination;
 use soroban_client::Server;
 use soroban_client::SimulationOptions;
 use std::collections::HashMap;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
+use tokio::time::{sleep, Instant};
+use tracing::field::Empty;
+use tracing::{info, instrument, warn, Span};
 
+use crate::capacity;
+use crate::capacity::LedgerCapacity;
+use crate::config::NetworkConfig;
+use crate::fee;
+use crate::fee::FeeConfig;
+use crate::grouping;
+use crate::grouping::ArgGroup;
+use crate::grouping::GroupConfig;
+use crate::headroom;
+#[cfg(feature = "sqlite")]
+use crate::persist::{MetricDb, RunContext};
+use crate::prometheus::PrometheusExporter;
+use crate::rent;
+use crate::rent::RentConfig;
+use crate::scaling;
+use crate::scaling::ScalingReport;
 use crate::show;
+use crate::spec;
 use crate::statistics;
 use crate::statistics::ResourceMetric;
-
-const WAIT_TIME: u64 = 10;
+use crate::trace;
+use crate::trace::TraceEvent;
+use crate::wasm;
+use crate::wasm::WasmReport;
+use crate::watcher::Watcher;
 
 #[derive(Debug, Clone)]
 pub struct HashMapValue {
     // send_tx_res: StellarTransactionResp,
-    transaction: Transaction,
-    sim_tx_res: SimulateTransactionResponse,
+    // (contract, function) the sample is stored under
+    pub(crate) keys: Vec<(String, String)>,
+    pub(crate) sim_tx_res: SimulateTransactionResponse,
 }
 
 pub type FunctionStore = HashMap<String, Vec<ResourceMetric>>;
@@ -45,59 +90,372 @@ pub type ContractStore = HashMap<String, FunctionStore>;
 
 #[derive(Debug)]
 pub struct StellarRpcServer {
-    inner: soroban_client::Server,
-    hash: HashMap<String, HashMapValue>,
+    inner: Arc<soroban_client::Server>,
+    // sent transactions not collected yet
+    watcher: Watcher,
     transaction: Option<Transaction>,
     sim_tx_res: Option<SimulateTransactionResponse>,
     store_stats: ContractStore,
+    rent_ledgers: u32,
+    wasm_reports: Vec<WasmReport>,
+    specs: HashMap<String, Vec<ScSpecEntry>>,
+    arg_groups: GroupConfig,
+    trace_path: Option<PathBuf>,
+    config: NetworkConfig,
+    // (contract, function, argument name)
+    scaling: Vec<(String, String, String)>,
+    // (contract, function, weight) of the mixed workload capacity
+    workload: Vec<(String, String, f64)>,
+    // source of the RestoreFootprint transactions, None to fail on
+    // archived entries
+    restore_keypair: Option<Keypair>,
+    #[cfg(feature = "sqlite")]
+    db: Option<(MetricDb, RunContext)>,
 }
 
 impl StellarRpcServer {
     pub fn new(url: &str, opts: Options) -> Result<Self, Error> {
         Ok(Self {
-            inner: Server::new(url, opts)?,
-            hash: HashMap::new(),
+            inner: Arc::new(Server::new(url, opts)?),
+            watcher: Watcher::default(),
             transaction: None,
             sim_tx_res: None,
             store_stats: HashMap::new(),
+            rent_ledgers: rent::DEFAULT_RENT_LEDGERS,
+            wasm_reports: vec![],
+            specs: HashMap::new(),
+            arg_groups: HashMap::new(),
+            trace_path: None,
+            config: NetworkConfig::default(),
+            scaling: vec![],
+            workload: vec![],
+            restore_keypair: None,
+            #[cfg(feature = "sqlite")]
+            db: None,
         })
     }
+
+    // checks the configured passphrase against the one of the rpc network
+    pub async fn with_config(
+        url: &str,
+        opts: Options,
+        config: NetworkConfig,
+    ) -> Result<Self, crate::Error> {
+        let mut server = Self::new(url, opts)?;
+        let network = server.get_network().await?;
+        let actual = network.passphrase.unwrap_or_default();
+        if actual != config.network.passphrase() {
+            return Err(crate::Error::NetworkMismatch {
+                expected: config.network.passphrase().to_string(),
+                actual,
+            });
+        }
+        server.config = config;
+        Ok(server)
+    }
+
+    pub fn config(&self) -> &NetworkConfig {
+        &self.config
+    }
+
+    // samples collected by the last flush, cleared by print_table
+    pub fn store(&self) -> &ContractStore {
+        &self.store_stats
+    }
+
+    // ledgers used to estimate the rent in the storage table
+    pub fn set_rent_ledgers(&mut self, ledgers: u32) {
+        self.rent_ledgers = ledgers;
+    }
+    // static report of a wasm file, printed with the resource tables
+    pub fn analyze_wasm(&mut self, path: &str) -> Result<WasmReport, crate::Error> {
+        let wasm = std::fs::read(path)?;
+        let report = wasm::analyze_wasm(&wasm)?;
+        self.wasm_reports.retain(|r| r.hash != report.hash);
+        self.wasm_reports.push(report.clone());
+        Ok(report)
+    }
+
+    // fetch the contract spec used to decode arguments
+    pub async fn load_contract_spec(&mut self, contract_id: &str) -> Result<(), crate::Error> {
+        let wasm = self.get_contract_wasm(contract_id).await?;
+        let spec = wasm::contract_spec(&wasm)?;
+        self.specs.insert(contract_id.to_string(), spec);
+        Ok(())
+    }
+
+    pub fn contract_spec(&self, contract_id: &str) -> Option<&[ScSpecEntry]> {
+        self.specs.get(contract_id).map(|s| s.as_slice())
+    }
+
+    // split the samples of `function` by the value of its argument `arg`,
+    // needs the contract spec from load_contract_spec
+    pub fn group_by_arg(&mut self, contract_id: &str, function: &str, arg: &str, group: ArgGroup) {
+        self.arg_groups.insert(
+            (contract_id.to_string(), function.to_string()),
+            (arg.to_string(), group),
+        );
+    }
+
+    // fit every metric of `function` against its argument `arg` and print
+    // the input size at which each network limit would be hit, needs the
+    // contract spec from load_contract_spec
+    pub fn scale_by_arg(&mut self, contract_id: &str, function: &str, arg: &str) {
+        let entry = (
+            contract_id.to_string(),
+            function.to_string(),
+            arg.to_string(),
+        );
+        if !self.scaling.contains(&entry) {
+            self.scaling.push(entry);
+        }
+    }
+
+    // include `function` in the mixed workload with a relative `weight`,
+    // e.g. 3 transfers for 1 mint
+    pub fn add_workload(&mut self, contract_id: &str, function: &str, weight: f64) {
+        self.workload
+            .push((contract_id.to_string(), function.to_string(), weight));
+    }
+
+    // restore the archived entries reported by the simulation in
+    // prepare_transaction, with a RestoreFootprint transaction signed by
+    // `keypair`. its cost is collected as "<function>:restore"
+    pub fn set_auto_restore(&mut self, keypair: Keypair) {
+        self.restore_keypair = Some(keypair);
+    }
+
+    fn scaling_reports(&self, limits: &HashMap<&'static str, u64>) -> Vec<ScalingReport> {
+        self.scaling
+            .iter()
+            .filter_map(|(contract, function, arg)| {
+                let spec = self.specs.get(contract)?;
+                let index = spec::find_function(spec, function)?
+                    .inputs
+                    .iter()
+                    .position(|input| input.name.to_string() == *arg)?;
+                let samples = self.store_stats.get(contract)?.get(function)?;
+                scaling::scaling_report(contract, function, arg, index, samples, limits)
+            })
+            .collect()
+    }
+
+    // append every collected sample to the sqlite database at `path`,
+    // returns the context of this run
+    #[cfg(feature = "sqlite")]
+    pub fn persist_to(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<RunContext, crate::Error> {
+        let db = MetricDb::open(path)?;
+        let ctx = RunContext::new(self.config.network.name());
+        self.db = Some((db, ctx.clone()));
+        Ok(ctx)
+    }
+
+    #[cfg(feature = "sqlite")]
+    pub fn persist_with(&mut self, db: MetricDb, ctx: RunContext) {
+        self.db = Some((db, ctx));
+    }
+
+    // serve the confirmed samples as prometheus metrics on `addr`, e.g.
+    // "127.0.0.1:9184", returns the bound address
+    pub async fn serve_metrics(&mut self, addr: &str) -> Result<SocketAddr, crate::Error> {
+        let limits = self
+            .get_tx_limits()
+            .await
+            .unwrap_or_else(|_| show::stellar_limits_config());
+        let exporter = PrometheusExporter::serve(addr, self.config.network.name(), &limits)?;
+        let addr = exporter.addr();
+        self.watcher.set_exporter(exporter);
+        Ok(addr)
+    }
+
+    // append the diagnostic events of every collected transaction to `path`
+    // as json lines when the table is printed
+    pub fn set_trace_export(&mut self, path: impl Into<PathBuf>) {
+        self.trace_path = Some(path.into());
+    }
+
+    // decoded diagnostic events of a single transaction, in emission order
+    pub async fn trace_transaction(&self, hash: &str) -> Result<Vec<TraceEvent>, crate::Error> {
+        let tx_result = self.inner.get_transaction(hash).await?;
+        if tx_result.status == TransactionStatus::NotFound {
+            return Err(crate::Error::TransactionNotFound);
+        }
+        let (meta, _) = tx_result
+            .to_result_meta()
+            .ok_or(crate::Error::MissingMeta)?;
+        match meta {
+            TransactionMeta::V4(m) => Ok(trace::trace_events(&m.diagnostic_events)),
+            _ => Err(crate::Error::UnsupportedMeta),
+        }
+    }
+
+    pub async fn print_trace(&self, hash: &str) -> Result<(), crate::Error> {
+        let events = self.trace_transaction(hash).await?;
+        show::print_trace(hash, &events);
+        Ok(())
+    }
+
+    fn export_traces(&self, path: &std::path::Path) -> Result<(), crate::Error> {
+        let mut samples: Vec<&ResourceMetric> = self
+            .store_stats
+            .values()
+            .flat_map(|funcs| funcs.values().flatten())
+            .collect();
+        samples.sort_by_key(|s| s.ledger);
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        for sample in samples {
+            let lines = trace::to_json_lines(sample.tx_hash.as_deref(), &sample.diagnostic_events);
+            file.write_all(lines.as_bytes())?;
+        }
+        Ok(())
+    }
+
     //
     // override function
     //
+    #[instrument(skip_all, fields(contract = Empty, function = Empty, min_resource_fee = Empty, restore_fee = Empty, latest_ledger = Empty))]
     pub async fn simulate_transaction(
         &mut self,
         tx: &Transaction,
         leeway: Option<SimulationOptions>,
     ) -> Result<SimulateTransactionResponse, Error> {
+        record_keys(&Span::current(), &statistics::transaction_keys(tx));
         let sim = self.inner.simulate_transaction(tx, leeway).await?;
+        let span = Span::current();
+        span.record("latest_ledger", sim.latest_ledger);
+        if let Some(fee) = &sim.min_resource_fee {
+            span.record("min_resource_fee", fee.as_str());
+        }
+        if let Some(error) = &sim.error {
+            warn!(%error, "simulation failed");
+        }
+        if let Some((restore_fee, data)) = sim.to_restore_transaction_data() {
+            span.record("restore_fee", restore_fee);
+            warn!(
+                entries = data.resources.footprint.read_write.len(),
+                "simulation found archived entries"
+            );
+        }
         self.transaction = Some(tx.clone());
         self.sim_tx_res = Some(sim.clone());
         Ok(sim)
     }
 
+    // archived entries are restored first when auto restore is set, without
+    // it they fail with Error::RestoreRequired
     pub async fn prepare_transaction(
         &mut self,
         transaction: &Transaction,
-    ) -> Result<Transaction, Error> {
-        let sim_response = self.simulate_transaction(transaction, None).await?;
-        transaction::assemble_transaction(transaction, sim_response)
+    ) -> Result<Transaction, crate::Error> {
+        let sim_response = self
+            .simulate_transaction(transaction, self.config.simulation_options())
+            .await?;
+        if !self.restore_footprint(transaction, &sim_response).await? {
+            return Ok(transaction::assemble_transaction(
+                transaction,
+                sim_response,
+            )?);
+        }
+        let mut transaction = transaction.clone();
+        if transaction.source.as_deref() == self.restore_account().as_deref() {
+            // the restore used the next sequence number of the source
+            let sequence = transaction.sequence.as_deref().unwrap_or("0");
+            let sequence = i64::from_str(sequence)
+                .map_err(|e| crate::Error::InvalidArgument(format!("sequence {sequence}: {e}")))?;
+            transaction.sequence = Some((sequence + 1).to_string());
+        }
+        let sim_response = self
+            .simulate_transaction(&transaction, self.config.simulation_options())
+            .await?;
+        Ok(transaction::assemble_transaction(
+            &transaction,
+            sim_response,
+        )?)
+    }
+
+    // submit a RestoreFootprint transaction when `sim` reports archived
+    // entries, and wait for the watcher to collect it under the restore keys
+    // of `tx`. returns false when nothing had to be restored, and
+    // Error::RestoreRequired when auto restore is not set
+    #[instrument(skip_all, fields(contract = Empty, function = Empty, tx_hash = Empty))]
+    pub async fn restore_footprint(
+        &mut self,
+        tx: &Transaction,
+        sim: &SimulateTransactionResponse,
+    ) -> Result<bool, crate::Error> {
+        let Some((_, data)) = sim.to_restore_transaction_data() else {
+            return Ok(false);
+        };
+        let Some(keypair) = self.restore_keypair.clone() else {
+            return Err(crate::Error::RestoreRequired);
+        };
+        let keys = statistics::restore_keys(tx);
+        record_keys(&Span::current(), &keys);
+
+        let op = Operation::new()
+            .restore_footprint()
+            .map_err(|e| crate::Error::InvalidArgument(format!("{e:?}")))?;
+        let mut account = self.get_account(&keypair.public_key()).await?;
+        let restore = TransactionBuilder::new(&mut account, self.config.network.passphrase(), None)
+            .fee(self.config.base_fee)
+            .add_operation(op)
+            .set_soroban_data(data)
+            .build();
+        // simulated like any other call, its transaction data is needed to
+        // collect the resources
+        let restore_sim = self.inner.simulate_transaction(&restore, None).await?;
+        let mut restore = transaction::assemble_transaction(&restore, restore_sim.clone())?;
+        restore.sign(&[keypair]);
+
+        let res = self.inner.send_transaction(restore).await?;
+        Span::current().record("tx_hash", res.hash.as_str());
+        self.watcher.watch(
+            &self.inner,
+            &self.config,
+            res.hash.clone(),
+            HashMapValue {
+                keys,
+                sim_tx_res: restore_sim,
+            },
+            res.latest_ledger,
+        );
+        if !self.watcher.wait(&res.hash).await {
+            return Err(crate::Error::TransactionFailed(res.hash));
+        }
+        info!("archived entries restored");
+        Ok(true)
+    }
+
+    fn restore_account(&self) -> Option<String> {
+        self.restore_keypair.as_ref().map(|k| k.public_key())
     }
 
+    #[instrument(skip_all, fields(contract = Empty, function = Empty, tx_hash = Empty, status = Empty))]
     pub async fn send_transaction(
         &mut self,
         tx: Transaction,
     ) -> Result<SendTransactionResponse, Error> {
-        // println!("send_trasaction: sim_tx_res:{:?}", self.sim_tx_res);
-        // println!("send_trasaction: trasaction:{:?}", self.transaction);
+        record_keys(&Span::current(), &statistics::transaction_keys(&tx));
         let res = self.inner.send_transaction(tx.clone()).await?;
+        Span::current()
+            .record("tx_hash", res.hash.as_str())
+            .record("status", tracing::field::debug(&res.status));
         if let (Some(sim), Some(prev_tx)) = (&self.sim_tx_res, &self.transaction) {
-            self.hash.insert(
+            self.watcher.watch(
+                &self.inner,
+                &self.config,
                 res.hash.clone(),
                 HashMapValue {
-                    transaction: prev_tx.clone(),
+                    keys: statistics::transaction_keys(prev_tx),
                     sim_tx_res: sim.clone(),
                 },
+                res.latest_ledger,
             );
         }
         self.transaction = None;
@@ -105,42 +463,160 @@ impl StellarRpcServer {
         Ok(res)
     }
 
+    // wait until every sent transaction is collected or timed out
+    pub async fn flush(&mut self) -> Result<(), crate::Error> {
+        let mut res = Ok(());
+        for resolved in self.watcher.flush().await {
+            match resolved.stats {
+                Ok(stats) => {
+                    statistics::store_transaction(&mut self.store_stats, &resolved.keys, &stats)
+                }
+                Err(e) => res = res.and(Err(e)),
+            }
+        }
+        res
+    }
+
     pub async fn print_table(&mut self) -> Result<(), crate::Error> {
-        let hashes: Vec<String> = self.hash.keys().cloned().collect();
-        let server = &self.inner;
-        let futures = hashes.iter().map(|h| {
-            let h = h.clone();
-            async move {
-                let res = server
-                    .wait_transaction(&h, Duration::from_secs(WAIT_TIME))
-                    .await;
-                (h, res)
+        self.flush().await?;
+        // a trace file error must not drop the report
+        if let Some(path) = &self.trace_path {
+            if let Err(error) = self.export_traces(path) {
+                warn!(%error, path = %path.display(), "failed to export traces");
             }
-        });
-        let results = futures::future::join_all(futures).await;
-        for (hash, tx_result) in results {
-            let Ok(tx_result) = tx_result else {
-                println!("fail to get transaction");
-                continue;
-            };
-            if tx_result.status != TransactionStatus::Success {
-                println!("transaction status error: {:?}", tx_result.status);
-                continue;
-            };
-            let Some(map_value) = self.hash.get(&hash) else {
-                continue;
-            };
-            let stats = statistics::handle_transaction(&map_value.sim_tx_res, &tx_result)?;
-            statistics::store_transaction(&mut self.store_stats, &map_value.transaction, &stats);
-        }
-        for (constract_id, _) in &self.store_stats {
-            show::print_table(constract_id, &self.store_stats)
-        }
-        self.hash.clear();
+        }
+        #[cfg(feature = "sqlite")]
+        if let Some((db, ctx)) = &mut self.db {
+            db.insert_store(ctx, &self.store_stats)?;
+        }
+        let store = grouping::group_store(&self.store_stats, &self.specs, &self.arg_groups);
+        let tx_limits = self
+            .get_tx_limits()
+            .await
+            .unwrap_or_else(|_| show::stellar_limits_config());
+        let ledger = self.get_ledger_capacity().await.unwrap_or_default();
+        for constract_id in store.keys() {
+            show::print_table_with_limits(constract_id, &store, &tx_limits);
+            show::print_headroom(constract_id, &store, &tx_limits, &ledger);
+            show::print_footprint(constract_id, &store);
+            show::print_events(constract_id, &store);
+            show::print_auth(constract_id, &store);
+        }
+        let rent_config = self.get_rent_config().await.ok();
+        show::print_storage(&store, rent_config.as_ref(), self.rent_ledgers);
+        for report in &self.wasm_reports {
+            show::print_wasm(report);
+        }
+        for report in self.scaling_reports(&tx_limits) {
+            show::print_scaling(&report);
+        }
+        let workload = capacity::workload_capacity(&self.store_stats, &ledger, &self.workload);
+        show::print_capacity(
+            &capacity::capacity(&self.store_stats, &ledger),
+            &ledger,
+            workload.as_ref(),
+        );
+        self.watcher.clear();
         self.store_stats.clear();
         Ok(())
     }
 
+    pub async fn get_config_settings(
+        &self,
+        ids: &[ConfigSettingId],
+    ) -> Result<Vec<ConfigSettingEntry>, Error> {
+        let keys = ids
+            .iter()
+            .map(|id| {
+                LedgerKey::ConfigSetting(LedgerKeyConfigSetting {
+                    config_setting_id: *id,
+                })
+            })
+            .collect();
+        let res = self.inner.get_ledger_entries(keys).await?;
+        let settings = res
+            .entries
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|entry| match entry.to_data() {
+                LedgerEntryData::ConfigSetting(setting) => Some(setting),
+                _ => None,
+            })
+            .collect();
+        Ok(settings)
+    }
+
+    pub async fn get_rent_config(&self) -> Result<RentConfig, crate::Error> {
+        let settings = self
+            .get_config_settings(&rent::RENT_CONFIG_SETTINGS)
+            .await?;
+        RentConfig::from_settings(&settings).ok_or(crate::Error::MissingConfigSetting)
+    }
+
+    // per-transaction metric limits, defaults for the limits the network
+    // settings do not cover
+    pub async fn get_tx_limits(&self) -> Result<HashMap<&'static str, u64>, crate::Error> {
+        let settings = self.get_config_settings(&show::TX_LIMIT_SETTINGS).await?;
+        Ok(show::stellar_limits_from_settings(&settings))
+    }
+
+    // ledger-wide limits keyed like the per-transaction metric limits
+    pub async fn get_ledger_limits(&self) -> Result<HashMap<&'static str, u64>, crate::Error> {
+        let settings = self
+            .get_config_settings(&headroom::LEDGER_LIMIT_SETTINGS)
+            .await?;
+        headroom::ledger_limits_from_settings(&settings).ok_or(crate::Error::MissingConfigSetting)
+    }
+
+    // ledger limits, tx count, parallel clusters and close time
+    pub async fn get_ledger_capacity(&self) -> Result<LedgerCapacity, crate::Error> {
+        let settings = self
+            .get_config_settings(&capacity::CAPACITY_SETTINGS)
+            .await?;
+        LedgerCapacity::from_settings(&settings).ok_or(crate::Error::MissingConfigSetting)
+    }
+
+    pub async fn get_fee_config(&self) -> Result<FeeConfig, crate::Error> {
+        let settings = self.get_config_settings(&fee::FEE_CONFIG_SETTINGS).await?;
+        FeeConfig::from_settings(&settings).ok_or(crate::Error::MissingConfigSetting)
+    }
+
+    // wasm code of a deployed contract, through its instance entry
+    pub async fn get_contract_wasm(&self, contract_id: &str) -> Result<Vec<u8>, crate::Error> {
+        let instance_key = LedgerKey::ContractData(LedgerKeyContractData {
+            contract: ScAddress::from_str(contract_id)?,
+            key: ScVal::LedgerKeyContractInstance,
+            durability: ContractDataDurability::Persistent,
+        });
+        let res = self.inner.get_ledger_entries(vec![instance_key]).await?;
+        let wasm_hash = res
+            .entries
+            .unwrap_or_default()
+            .iter()
+            .find_map(|entry| match entry.to_data() {
+                LedgerEntryData::ContractData(data) => match data.val {
+                    ScVal::ContractInstance(instance) => match instance.executable {
+                        ContractExecutable::Wasm(hash) => Some(hash),
+                        ContractExecutable::StellarAsset => None,
+                    },
+                    _ => None,
+                },
+                _ => None,
+            })
+            .ok_or(crate::Error::MissingContractWasm)?;
+
+        let code_key = LedgerKey::ContractCode(LedgerKeyContractCode { hash: wasm_hash });
+        let res = self.inner.get_ledger_entries(vec![code_key]).await?;
+        res.entries
+            .unwrap_or_default()
+            .iter()
+            .find_map(|entry| match entry.to_data() {
+                LedgerEntryData::ContractCode(code) => Some(code.code.to_vec()),
+                _ => None,
+            })
+            .ok_or(crate::Error::MissingContractWasm)
+    }
+
     //
     // inner function
     //
@@ -218,11 +694,41 @@ impl StellarRpcServer {
         self.inner.request_airdrop(account_id).await
     }
 
+    #[instrument(skip_all, fields(tx_hash = hash, status = Empty, elapsed_ms = Empty))]
     pub async fn wait_transaction(
         &self,
         hash: &str,
         max_wait: Duration,
     ) -> Result<GetTransactionResponse, (Error, Option<GetTransactionResponse>)> {
-        self.inner.wait_transaction(hash, max_wait).await
+        let start = Instant::now();
+        let mut last_response = None;
+        while start.elapsed() < max_wait {
+            match self.inner.get_transaction(hash).await {
+                Ok(tx) if tx.status == TransactionStatus::NotFound => {
+                    last_response = Some(tx);
+                    sleep(self.config.poll_interval).await;
+                }
+                Ok(tx) => {
+                    Span::current()
+                        .record("status", tracing::field::debug(&tx.status))
+                        .record("elapsed_ms", start.elapsed().as_millis() as u64);
+                    return Ok(tx);
+                }
+                Err(e) => return Err((e, last_response)),
+            }
+        }
+        warn!(tx_hash = hash, "transaction wait timed out");
+        Err((
+            Error::WaitTransactionTimeout(max_wait.as_secs(), start.elapsed().as_secs()),
+            last_response,
+        ))
     }
 }
+
+// contracts and functions of `keys`, comma separated
+pub(crate) fn record_keys(span: &Span, keys: &[(String, String)]) {
+    let contracts: Vec<&str> = keys.iter().map(|(c, _)| c.as_str()).collect();
+    let functions: Vec<&str> = keys.iter().map(|(_, f)| f.as_str()).collect();
+    span.record("contract", contracts.join(","))
+        .record("function", functions.join(","));
+}
@@ -0,0 +1,342 @@
+use soroban_client::xdr::ScVal;
+use std::collections::HashMap;
+
+use crate::{scval_tools, show, statistics::ResourceMetric};
+
+// quadratic model only kept when it explains at least this much more
+// variance than the linear one
+const QUADRATIC_MIN_GAIN: f64 = 0.01;
+
+// cost = a + b * x + c * x^2, c is 0 for a linear fit
+#[derive(Debug, Clone, Copy)]
+pub struct Fit {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub r2: f64,
+}
+
+impl Fit {
+    pub fn is_quadratic(&self) -> bool {
+        self.c != 0.0
+    }
+
+    pub fn predict(&self, x: f64) -> f64 {
+        self.a + self.b * x + self.c * x * x
+    }
+
+    // smallest non negative input at which the cost reaches `limit`
+    pub fn solve(&self, limit: f64) -> Option<f64> {
+        if self.a >= limit {
+            return Some(0.0);
+        }
+        let roots = if self.is_quadratic() {
+            let disc = self.b * self.b - 4.0 * self.c * (self.a - limit);
+            if disc < 0.0 {
+                return None;
+            }
+            let sqrt = disc.sqrt();
+            vec![
+                (-self.b + sqrt) / (2.0 * self.c),
+                (-self.b - sqrt) / (2.0 * self.c),
+            ]
+        } else if self.b > 0.0 {
+            vec![(limit - self.a) / self.b]
+        } else {
+            return None;
+        };
+        roots
+            .into_iter()
+            .filter(|x| x.is_finite() && *x >= 0.0)
+            .min_by(|x, y| x.total_cmp(y))
+    }
+
+    pub fn formula(&self) -> String {
+        if self.is_quadratic() {
+            format!("{:.2} + {:.2}x + {:.4}x²", self.a, self.b, self.c)
+        } else {
+            format!("{:.2} + {:.2}x", self.a, self.b)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MetricScaling {
+    pub metric: &'static str,
+    pub fit: Fit,
+    pub limit: Option<u64>,
+    // largest input keeping the metric under its limit
+    pub max_safe: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScalingReport {
+    pub contract: String,
+    pub function: String,
+    pub arg: String,
+    // (min, max) of the swept input
+    pub range: (f64, f64),
+    pub samples: usize,
+    pub metrics: Vec<MetricScaling>,
+}
+
+// numeric value of an integer argument, length of a collection
+pub fn input_size(val: &ScVal) -> Option<f64> {
+    match val {
+        ScVal::Vec(Some(v)) => Some(v.len() as f64),
+        ScVal::Map(Some(m)) => Some(m.len() as f64),
+        ScVal::Bytes(b) => Some(b.len() as f64),
+        ScVal::String(s) => Some(s.len() as f64),
+        ScVal::Symbol(s) => Some(s.len() as f64),
+        _ => scval_tools::scval_as_i128(val).map(|n| n as f64),
+    }
+}
+
+// fit every metric against the argument at `index`, max safe inputs are
+// solved against the per-transaction `limits`
+pub fn scaling_report(
+    contract: &str,
+    function: &str,
+    arg: &str,
+    index: usize,
+    samples: &[ResourceMetric],
+    limits: &HashMap<&'static str, u64>,
+) -> Option<ScalingReport> {
+    let inputs: Vec<(f64, &ResourceMetric)> = samples
+        .iter()
+        .filter_map(|s| Some((input_size(s.args.get(index)?)?, s)))
+        .collect();
+    let xs: Vec<f64> = inputs.iter().map(|(x, _)| *x).collect();
+    let min = xs.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = xs.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if xs.len() < 2 || min == max {
+        return None;
+    }
+
+    let mut res = vec![];
+    for key in show::METRIC_KEYS {
+        let points: Vec<(f64, f64)> = inputs
+            .iter()
+            .filter_map(|(x, s)| Some((*x, show::get_metric_u64(s, key)? as f64)))
+            .collect();
+        let Some(fit) = best_fit(&points) else {
+            continue;
+        };
+        let limit = limits.get(key).copied();
+        let max_safe = limit
+            .and_then(|l| fit.solve(l as f64))
+            .map(|x| x.floor() as u64);
+        res.push(MetricScaling {
+            metric: key,
+            fit,
+            limit,
+            max_safe,
+        });
+    }
+    Some(ScalingReport {
+        contract: contract.to_string(),
+        function: function.to_string(),
+        arg: arg.to_string(),
+        range: (min, max),
+        samples: inputs.len(),
+        metrics: res,
+    })
+}
+
+pub fn best_fit(points: &[(f64, f64)]) -> Option<Fit> {
+    let linear = fit_linear(points)?;
+    let mut distinct: Vec<f64> = points.iter().map(|(x, _)| *x).collect();
+    distinct.sort_by(|a, b| a.total_cmp(b));
+    distinct.dedup();
+    if distinct.len() < 4 {
+        return Some(linear);
+    }
+    match fit_quadratic(points) {
+        Some(quad) if quad.r2 - linear.r2 > QUADRATIC_MIN_GAIN => Some(quad),
+        _ => Some(linear),
+    }
+}
+
+fn fit_linear(points: &[(f64, f64)]) -> Option<Fit> {
+    let n = points.len() as f64;
+    let sx: f64 = points.iter().map(|(x, _)| x).sum();
+    let sy: f64 = points.iter().map(|(_, y)| y).sum();
+    let sxx: f64 = points.iter().map(|(x, _)| x * x).sum();
+    let sxy: f64 = points.iter().map(|(x, y)| x * y).sum();
+    let denom = n * sxx - sx * sx;
+    if n < 2.0 || denom == 0.0 {
+        return None;
+    }
+    let b = (n * sxy - sx * sy) / denom;
+    let a = (sy - b * sx) / n;
+    let mut fit = Fit {
+        a,
+        b,
+        c: 0.0,
+        r2: 0.0,
+    };
+    fit.r2 = r_squared(points, &fit);
+    Some(fit)
+}
+
+// least squares through the normal equations
+fn fit_quadratic(points: &[(f64, f64)]) -> Option<Fit> {
+    let mut m = [[0.0f64; 4]; 3];
+    for (x, y) in points {
+        let powers = [1.0, *x, x * x];
+        for i in 0..3 {
+            for j in 0..3 {
+                m[i][j] += powers[i] * powers[j];
+            }
+            m[i][3] += powers[i] * y;
+        }
+    }
+    let [a, b, c] = solve3(m)?;
+    let mut fit = Fit { a, b, c, r2: 0.0 };
+    fit.r2 = r_squared(points, &fit);
+    Some(fit)
+}
+
+// gaussian elimination with partial pivoting on an augmented 3x4 matrix
+fn solve3(mut m: [[f64; 4]; 3]) -> Option<[f64; 3]> {
+    for col in 0..3 {
+        let pivot = (col..3).max_by(|i, j| m[*i][col].abs().total_cmp(&m[*j][col].abs()))?;
+        if m[pivot][col].abs() < f64::EPSILON {
+            return None;
+        }
+        m.swap(col, pivot);
+        let pivot_row = m[col];
+        for (row, r) in m.iter_mut().enumerate() {
+            if row != col {
+                let factor = r[col] / pivot_row[col];
+                for (v, p) in r.iter_mut().zip(pivot_row.iter()).skip(col) {
+                    *v -= factor * p;
+                }
+            }
+        }
+    }
+    Some([m[0][3] / m[0][0], m[1][3] / m[1][1], m[2][3] / m[2][2]])
+}
+
+fn r_squared(points: &[(f64, f64)], fit: &Fit) -> f64 {
+    let mean = points.iter().map(|(_, y)| y).sum::<f64>() / points.len() as f64;
+    let ss_tot: f64 = points.iter().map(|(_, y)| (y - mean).powi(2)).sum();
+    let ss_res: f64 = points
+        .iter()
+        .map(|(x, y)| (y - fit.predict(*x)).powi(2))
+        .sum();
+    if ss_tot == 0.0 {
+        return 1.0;
+    }
+    1.0 - ss_res / ss_tot
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6 * expected.abs().max(1.0),
+            "{actual} != {expected}"
+        );
+    }
+
+    #[test]
+    fn test_linear() {
+        let points: Vec<(f64, f64)> = (0..6)
+            .map(|x| (x as f64, 100.0 + 20.0 * x as f64))
+            .collect();
+        let fit = best_fit(&points).unwrap();
+        assert!(!fit.is_quadratic());
+        assert_close(fit.a, 100.0);
+        assert_close(fit.b, 20.0);
+        assert_close(fit.r2, 1.0);
+        // 100 + 20x = 1000
+        assert_close(fit.solve(1000.0).unwrap(), 45.0);
+    }
+
+    #[test]
+    fn test_quadratic() {
+        let points: Vec<(f64, f64)> = (0..8)
+            .map(|x| {
+                let x = x as f64;
+                (x, 5.0 + 3.0 * x + 2.0 * x * x)
+            })
+            .collect();
+        let fit = best_fit(&points).unwrap();
+        assert!(fit.is_quadratic());
+        assert_close(fit.a, 5.0);
+        assert_close(fit.b, 3.0);
+        assert_close(fit.c, 2.0);
+        // 5 + 3x + 2x² = 235 at x = 10, the negative root -11.5 is ignored
+        assert_close(fit.solve(235.0).unwrap(), 10.0);
+    }
+
+    #[test]
+    fn test_degenerate() {
+        // a single distinct input cannot be fitted
+        assert!(best_fit(&[(3.0, 10.0), (3.0, 12.0), (3.0, 11.0)]).is_none());
+        assert!(best_fit(&[(1.0, 10.0)]).is_none());
+        assert!(solve3([[0.0; 4]; 3]).is_none());
+        // two equal rows
+        assert!(solve3([
+            [1.0, 2.0, 3.0, 1.0],
+            [1.0, 2.0, 3.0, 1.0],
+            [0.0, 1.0, 1.0, 2.0],
+        ])
+        .is_none());
+        assert_eq!(
+            solve3([
+                [2.0, 0.0, 0.0, 4.0],
+                [0.0, 0.0, 3.0, 9.0],
+                [0.0, 5.0, 0.0, 10.0],
+            ]),
+            Some([2.0, 2.0, 3.0])
+        );
+    }
+
+    #[test]
+    fn test_solve_unreached() {
+        let flat = Fit {
+            a: 10.0,
+            b: 0.0,
+            c: 0.0,
+            r2: 1.0,
+        };
+        assert_eq!(flat.solve(100.0), None);
+        let decreasing = Fit { b: -1.0, ..flat };
+        assert_eq!(decreasing.solve(100.0), None);
+        // peaks at 10 + 25 = 35 for x = 5
+        let concave = Fit {
+            b: 10.0,
+            c: -1.0,
+            ..flat
+        };
+        assert_eq!(concave.solve(100.0), None);
+        // already over the limit without input
+        assert_eq!(flat.solve(5.0), Some(0.0));
+    }
+
+    #[test]
+    fn test_report_limits() {
+        let samples: Vec<ResourceMetric> = (1..5)
+            .map(|x| ResourceMetric {
+                cpu_insns: Some(1_000 + 100 * x),
+                args: vec![ScVal::U32(x as u32)],
+                ..Default::default()
+            })
+            .collect();
+        let limits = HashMap::from([("cpu_insns", 11_000)]);
+        let report = scaling_report("C1", "loop", "n", 0, &samples, &limits).unwrap();
+        assert_eq!(report.range, (1.0, 4.0));
+        let cpu = report
+            .metrics
+            .iter()
+            .find(|m| m.metric == "cpu_insns")
+            .unwrap();
+        // 1000 + 100x = 11000
+        assert_eq!(cpu.limit, Some(11_000));
+        assert_eq!(cpu.max_safe, Some(100));
+    }
+}
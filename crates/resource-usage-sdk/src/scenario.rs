@@ -0,0 +1,412 @@
+use serde::Deserialize;
+use soroban_client::xdr::{
+    Int128Parts, ScAddress, ScBytes, ScString, ScSymbol, ScVal, ScVec, UInt128Parts,
+};
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use tracing::warn;
+
+use crate::{spec, ArgGroup, Client, Error};
+
+// a workload read from a toml or json file:
+//
+//   [[accounts]]
+//   name = "alice"
+//
+//   [[contracts]]
+//   name = "token"
+//   wasm = "target/wasm32v1-none/release/token.wasm"
+//   constructor_args = [{ address = "alice" }]
+//
+//   [[steps]]
+//   contract = "token"
+//   function = "transfer"
+//   source = "alice"
+//   args = [{ address = "alice" }, { address = "token" }, { i128 = 100 }]
+//   repeat = 5
+//   sweep = { arg = 2, from = 100, to = 10000, step = 1000 }
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    #[serde(default)]
+    pub accounts: Vec<AccountSpec>,
+    #[serde(default)]
+    pub contracts: Vec<ContractSpec>,
+    #[serde(default)]
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountSpec {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContractSpec {
+    pub name: String,
+    pub wasm: String,
+    #[serde(default)]
+    pub constructor_args: Vec<ArgSpec>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Step {
+    // contract name from `contracts` or a contract id
+    pub contract: String,
+    pub function: String,
+    // registered account used as transaction source, the harness source
+    // account if not set
+    pub source: Option<String>,
+    #[serde(default)]
+    pub args: Vec<ArgSpec>,
+    #[serde(default = "default_repeat")]
+    pub repeat: u32,
+    pub sweep: Option<Sweep>,
+}
+
+fn default_repeat() -> u32 {
+    1
+}
+
+// replace the argument at index `arg` by each of `values`, or by each
+// integer of from..=to, keeping the integer type of the argument
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Sweep {
+    pub arg: usize,
+    #[serde(default)]
+    pub values: Vec<ArgSpec>,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub step: Option<i64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum IntSpec {
+    Num(i64),
+    Text(String),
+}
+
+impl IntSpec {
+    fn parse<T: FromStr + TryFrom<i64>>(&self) -> Result<T, Error> {
+        let res = match self {
+            IntSpec::Num(n) => T::try_from(*n).ok(),
+            IntSpec::Text(s) => s.parse().ok(),
+        };
+        res.ok_or_else(|| Error::ScenarioError(format!("invalid integer {self:?}")))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum ArgSpec {
+    // written as the plain string "void"
+    Void,
+    Bool(bool),
+    U32(u32),
+    I32(i32),
+    U64(IntSpec),
+    I64(IntSpec),
+    U128(IntSpec),
+    I128(IntSpec),
+    // hex
+    Bytes(String),
+    String(String),
+    Symbol(String),
+    // account or contract name, or a strkey
+    Address(String),
+    Vec(Vec<ArgSpec>),
+}
+
+impl ArgSpec {
+    // same integer type with another value
+    fn with_int(&self, value: i64) -> Result<ArgSpec, Error> {
+        let out_of_range = || Error::ScenarioError(format!("{value} out of range for {self:?}"));
+        Ok(match self {
+            ArgSpec::U32(_) => ArgSpec::U32(value.try_into().map_err(|_| out_of_range())?),
+            ArgSpec::I32(_) => ArgSpec::I32(value.try_into().map_err(|_| out_of_range())?),
+            ArgSpec::U64(_) => ArgSpec::U64(IntSpec::Num(value)),
+            ArgSpec::I64(_) => ArgSpec::I64(IntSpec::Num(value)),
+            ArgSpec::U128(_) => ArgSpec::U128(IntSpec::Num(value)),
+            ArgSpec::I128(_) => ArgSpec::I128(IntSpec::Num(value)),
+            _ => {
+                return Err(Error::ScenarioError(format!(
+                    "cannot sweep a range over {self:?}"
+                )))
+            }
+        })
+    }
+
+    pub fn to_scval(&self, names: &HashMap<String, String>) -> Result<ScVal, Error> {
+        Ok(match self {
+            ArgSpec::Void => ScVal::Void,
+            ArgSpec::Bool(b) => ScVal::Bool(*b),
+            ArgSpec::U32(n) => ScVal::U32(*n),
+            ArgSpec::I32(n) => ScVal::I32(*n),
+            ArgSpec::U64(n) => ScVal::U64(n.parse()?),
+            ArgSpec::I64(n) => ScVal::I64(n.parse()?),
+            ArgSpec::U128(n) => {
+                let n: u128 = n.parse()?;
+                ScVal::U128(UInt128Parts {
+                    hi: (n >> 64) as u64,
+                    lo: n as u64,
+                })
+            }
+            ArgSpec::I128(n) => {
+                let n: i128 = n.parse()?;
+                ScVal::I128(Int128Parts {
+                    hi: (n >> 64) as i64,
+                    lo: n as u64,
+                })
+            }
+            ArgSpec::Bytes(s) => {
+                let bytes =
+                    hex::decode(s).map_err(|_| Error::ScenarioError(format!("invalid hex {s}")))?;
+                ScVal::Bytes(ScBytes(bytes.try_into()?))
+            }
+            ArgSpec::String(s) => ScVal::String(ScString(s.as_str().try_into()?)),
+            ArgSpec::Symbol(s) => ScVal::Symbol(ScSymbol(s.as_str().try_into()?)),
+            ArgSpec::Address(s) => {
+                let address = names.get(s).unwrap_or(s);
+                ScVal::Address(ScAddress::from_str(address)?)
+            }
+            ArgSpec::Vec(items) => {
+                let items = items
+                    .iter()
+                    .map(|item| item.to_scval(names))
+                    .collect::<Result<Vec<_>, _>>()?;
+                ScVal::Vec(Some(ScVec(items.try_into()?)))
+            }
+        })
+    }
+}
+
+impl Sweep {
+    fn values(&self, template: &ArgSpec) -> Result<Vec<ArgSpec>, Error> {
+        let mut values = self.values.clone();
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            let step = self.step.unwrap_or(1);
+            if step <= 0 {
+                return Err(Error::ScenarioError("sweep step must be positive".into()));
+            }
+            let mut value = from;
+            while value <= to {
+                values.push(template.with_int(value)?);
+                // `to` close to i64::MAX
+                match value.checked_add(step) {
+                    Some(v) => value = v,
+                    None => break,
+                }
+            }
+        }
+        Ok(values)
+    }
+}
+
+impl Scenario {
+    // toml or json depending on the file extension
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Self::from_json(&content),
+            _ => Self::from_toml(&content),
+        }
+    }
+
+    pub fn from_toml(content: &str) -> Result<Self, Error> {
+        Ok(toml::from_str(content)?)
+    }
+
+    pub fn from_json(content: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(content)?)
+    }
+
+    // create the accounts, deploy the contracts, run every step and print
+    // the resource report
+    pub async fn run(&self, client: &mut Client) -> Result<(), Error> {
+        if self.steps.is_empty() {
+            warn!("scenario has no steps");
+        }
+        // name -> address of accounts and contracts
+        let mut names: HashMap<String, String> = HashMap::new();
+        for account in &self.accounts {
+            let address = client.add_account(&account.name).await?;
+            names.insert(account.name.clone(), address);
+        }
+        for contract in &self.contracts {
+            let args = contract
+                .constructor_args
+                .iter()
+                .map(|arg| arg.to_scval(&names))
+                .collect::<Result<Vec<_>, _>>()?;
+            client.server_mut().analyze_wasm(&contract.wasm)?;
+            let contract_id = client.deploy_wasm(&contract.wasm, args).await?;
+            client.server_mut().load_contract_spec(&contract_id).await?;
+            names.insert(contract.name.clone(), contract_id);
+        }
+
+        for step in &self.steps {
+            let contract_id = names.get(&step.contract).unwrap_or(&step.contract).clone();
+            let source = step.source.as_deref().unwrap_or(crate::SOURCE_ACCOUNT);
+            let runs = match &step.sweep {
+                Some(sweep) => {
+                    let template = step.args.get(sweep.arg).ok_or_else(|| {
+                        Error::ScenarioError(format!(
+                            "{}: sweep arg {} out of range",
+                            step.function, sweep.arg
+                        ))
+                    })?;
+                    let values = sweep.values(template)?;
+                    if values.is_empty() {
+                        warn!(
+                            function = %step.function,
+                            arg = sweep.arg,
+                            "sweep has no values, step skipped"
+                        );
+                        continue;
+                    }
+                    group_sweep(client, &contract_id, step, sweep.arg);
+                    values
+                        .into_iter()
+                        .map(|value| {
+                            let mut args = step.args.clone();
+                            args[sweep.arg] = value;
+                            args
+                        })
+                        .collect()
+                }
+                None => vec![step.args.clone()],
+            };
+            for args in runs {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.to_scval(&names))
+                    .collect::<Result<Vec<_>, _>>()?;
+                for _ in 0..step.repeat {
+                    client
+                        .invoke_as(source, &contract_id, &step.function, args.clone())
+                        .await?;
+                }
+            }
+        }
+        client.print_table().await
+    }
+}
+
+// report the swept argument as separate rows and fit the costs against it
+fn group_sweep(client: &mut Client, contract_id: &str, step: &Step, arg: usize) {
+    let wasm_spec = client.server().contract_spec(contract_id);
+    let arg_name = wasm_spec
+        .and_then(|s| spec::find_function(s, &step.function))
+        .and_then(|f| f.inputs.get(arg))
+        .map(|input| input.name.to_string());
+    let Some(arg_name) = arg_name else {
+        warn!(
+            contract = contract_id,
+            function = %step.function,
+            arg,
+            "no spec for the swept argument, sweep is not grouped or fitted"
+        );
+        return;
+    };
+    client
+        .server_mut()
+        .group_by_arg(contract_id, &step.function, &arg_name, ArgGroup::Value);
+    client
+        .server_mut()
+        .scale_by_arg(contract_id, &step.function, &arg_name);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TOML: &str = r#"
+        [[accounts]]
+        name = "alice"
+
+        [[contracts]]
+        name = "token"
+        wasm = "token.wasm"
+        constructor_args = [{ address = "alice" }]
+
+        [[steps]]
+        contract = "token"
+        function = "transfer"
+        source = "alice"
+        args = [{ address = "alice" }, "void", { i128 = "-100" }]
+        repeat = 5
+        sweep = { arg = 2, from = 100, to = 10000, step = 1000 }
+    "#;
+
+    #[test]
+    fn test_from_toml() {
+        let scenario = Scenario::from_toml(TOML).unwrap();
+        assert_eq!(scenario.accounts[0].name, "alice");
+        assert_eq!(scenario.contracts[0].wasm, "token.wasm");
+        let step = &scenario.steps[0];
+        assert_eq!(step.source.as_deref(), Some("alice"));
+        assert_eq!(step.repeat, 5);
+        assert!(matches!(step.args[1], ArgSpec::Void));
+        let names = HashMap::new();
+        let ScVal::I128(n) = step.args[2].to_scval(&names).unwrap() else {
+            panic!("not an i128");
+        };
+        assert_eq!((n.hi, n.lo), (-1, -100i64 as u64));
+        let sweep = step.sweep.as_ref().unwrap();
+        assert_eq!((sweep.arg, sweep.step), (2, Some(1000)));
+
+        assert!(Scenario::from_toml("[[steps]]\nunknown = 1").is_err());
+    }
+
+    #[test]
+    fn test_from_json() {
+        let scenario = Scenario::from_json(
+            r#"{"steps": [{"contract": "C", "function": "f", "args": [{"u64": 7}]}]}"#,
+        )
+        .unwrap();
+        assert!(scenario.accounts.is_empty());
+        let step = &scenario.steps[0];
+        assert_eq!(step.repeat, 1);
+        assert!(step.sweep.is_none());
+        assert!(matches!(step.args[0], ArgSpec::U64(IntSpec::Num(7))));
+    }
+
+    #[test]
+    fn test_sweep_values() {
+        let sweep = |from, to, step| Sweep {
+            arg: 0,
+            values: vec![ArgSpec::U32(1)],
+            from: Some(from),
+            to: Some(to),
+            step,
+        };
+        let values = sweep(10, 30, Some(10)).values(&ArgSpec::U32(0)).unwrap();
+        let values: Vec<_> = values
+            .iter()
+            .map(|v| match v {
+                ArgSpec::U32(n) => *n,
+                _ => panic!("not a u32"),
+            })
+            .collect();
+        assert_eq!(values, vec![1, 10, 20, 30]);
+
+        // stops at i64::MAX instead of overflowing
+        let values = sweep(i64::MAX - 2, i64::MAX, Some(2))
+            .values(&ArgSpec::I64(IntSpec::Num(0)))
+            .unwrap();
+        assert_eq!(values.len(), 3);
+        assert!(matches!(values[2], ArgSpec::I64(IntSpec::Num(n)) if n == i64::MAX));
+
+        assert_eq!(sweep(5, 1, None).values(&ArgSpec::U32(0)).unwrap().len(), 1);
+        assert!(sweep(0, 1, Some(0)).values(&ArgSpec::U32(0)).is_err());
+        assert!(sweep(-1, 1, None).values(&ArgSpec::U32(0)).is_err());
+        assert!(sweep(0, 1, None).values(&ArgSpec::Bool(true)).is_err());
+    }
+}
@@ -1,4 +1,10 @@
-use soroban_client::xdr::ScVal;
+use ethnum::{I256, U256};
+use serde_json::{Map, Value};
+use soroban_client::xdr::{
+    ContractExecutable, ScError, ScSpecEntry, ScSpecTypeDef, ScSpecUdtUnionCaseV0, ScVal,
+};
+
+use crate::spec;
 
 pub fn scval_as_string(v: &ScVal) -> Option<String> {
     match v {
@@ -10,24 +16,416 @@ pub fn scval_as_string(v: &ScVal) -> Option<String> {
 
 pub fn scval_as_u64(v: &ScVal) -> Option<u64> {
     match v {
-        ScVal::U32(n) => Some(*n as u64),
-        ScVal::I32(n) => (*n).try_into().ok(),
         ScVal::U64(n) => Some(*n),
-        ScVal::I64(n) => (*n).try_into().ok(),
-        ScVal::I128(parts) => {
-            if parts.hi == 0 {
-                Some(parts.lo)
-            } else {
-                None
+        _ => scval_as_i128(v)
+            .and_then(|n| n.try_into().ok())
+            .or_else(|| scval_as_u128(v).and_then(|n| n.try_into().ok())),
+    }
+}
+
+pub fn scval_as_i128(v: &ScVal) -> Option<i128> {
+    match v {
+        ScVal::U32(n) => Some(*n as i128),
+        ScVal::I32(n) => Some(*n as i128),
+        ScVal::U64(n) => Some(*n as i128),
+        ScVal::I64(n) => Some(*n as i128),
+        ScVal::I128(parts) => Some(((parts.hi as i128) << 64) | parts.lo as i128),
+        ScVal::U128(_) => scval_as_u128(v).and_then(|n| n.try_into().ok()),
+        _ => None,
+    }
+}
+
+pub fn scval_as_u128(v: &ScVal) -> Option<u128> {
+    match v {
+        ScVal::U128(parts) => Some(((parts.hi as u128) << 64) | parts.lo as u128),
+        ScVal::U32(_) | ScVal::I32(_) | ScVal::U64(_) | ScVal::I64(_) | ScVal::I128(_) => {
+            scval_as_i128(v).and_then(|n| n.try_into().ok())
+        }
+        _ => None,
+    }
+}
+
+pub fn scval_as_i256(v: &ScVal) -> Option<I256> {
+    match v {
+        ScVal::I256(parts) => {
+            let hi = ((parts.hi_hi as i128) << 64) | parts.hi_lo as i128;
+            let lo = ((parts.lo_hi as u128) << 64) | parts.lo_lo as u128;
+            Some(I256::from_words(hi, lo as i128))
+        }
+        ScVal::U256(_) => scval_as_u256(v).and_then(|n| n.try_into().ok()),
+        _ => scval_as_i128(v)
+            .map(I256::from)
+            .or_else(|| scval_as_u128(v).map(I256::from)),
+    }
+}
+
+pub fn scval_as_u256(v: &ScVal) -> Option<U256> {
+    match v {
+        ScVal::U256(parts) => {
+            let hi = ((parts.hi_hi as u128) << 64) | parts.hi_lo as u128;
+            let lo = ((parts.lo_hi as u128) << 64) | parts.lo_lo as u128;
+            Some(U256::from_words(hi, lo))
+        }
+        ScVal::I256(_) => scval_as_i256(v).and_then(|n| n.try_into().ok()),
+        _ => scval_as_u128(v)
+            .map(U256::from)
+            .or_else(|| scval_as_i128(v).and_then(|n| U256::try_from(n).ok())),
+    }
+}
+
+// Bytes and BytesN
+pub fn scval_as_hex(v: &ScVal) -> Option<String> {
+    match v {
+        ScVal::Bytes(b) => Some(hex::encode(b.as_slice())),
+        _ => None,
+    }
+}
+
+// G... account or C... contract strkey
+pub fn scval_as_strkey(v: &ScVal) -> Option<String> {
+    match v {
+        ScVal::Address(a) => Some(a.to_string()),
+        _ => None,
+    }
+}
+
+pub fn scval_as_duration(v: &ScVal) -> Option<std::time::Duration> {
+    match v {
+        ScVal::Duration(d) => Some(std::time::Duration::from_secs(d.0)),
+        _ => None,
+    }
+}
+
+// unix timestamp in seconds
+pub fn scval_as_timepoint(v: &ScVal) -> Option<u64> {
+    match v {
+        ScVal::Timepoint(t) => Some(t.0),
+        _ => None,
+    }
+}
+
+// human readable form, e.g. `{amount: 100, to: GABC..}`
+pub fn scval_to_string(v: &ScVal) -> String {
+    match v {
+        ScVal::Void => "()".into(),
+        ScVal::Bool(b) => b.to_string(),
+        ScVal::Error(e) => error_to_string(e),
+        ScVal::U32(n) => n.to_string(),
+        ScVal::I32(n) => n.to_string(),
+        ScVal::U64(n) => n.to_string(),
+        ScVal::I64(n) => n.to_string(),
+        ScVal::Timepoint(t) => format!("timepoint({})", t.0),
+        ScVal::Duration(d) => format!("duration({}s)", d.0),
+        ScVal::U128(_) => scval_as_u128(v).unwrap_or_default().to_string(),
+        ScVal::I128(_) => scval_as_i128(v).unwrap_or_default().to_string(),
+        ScVal::U256(_) => scval_as_u256(v).unwrap_or_default().to_string(),
+        ScVal::I256(_) => scval_as_i256(v).unwrap_or_default().to_string(),
+        ScVal::Bytes(b) => format!("0x{}", hex::encode(b.as_slice())),
+        ScVal::String(s) => format!("{:?}", s.0.to_string()),
+        ScVal::Symbol(s) => s.0.to_string(),
+        ScVal::Vec(None) | ScVal::Map(None) => "()".into(),
+        ScVal::Vec(Some(items)) => {
+            let items: Vec<String> = items.iter().map(scval_to_string).collect();
+            format!("[{}]", items.join(", "))
+        }
+        ScVal::Map(Some(map)) => {
+            let entries: Vec<String> = map
+                .iter()
+                .map(|e| format!("{}: {}", scval_to_string(&e.key), scval_to_string(&e.val)))
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+        ScVal::Address(a) => a.to_string(),
+        ScVal::ContractInstance(instance) => match &instance.executable {
+            ContractExecutable::Wasm(hash) => format!("instance(wasm {})", hex::encode(hash.0)),
+            ContractExecutable::StellarAsset => "instance(stellar asset)".into(),
+        },
+        ScVal::LedgerKeyContractInstance => "instance".into(),
+        ScVal::LedgerKeyNonce(n) => format!("nonce({})", n.nonce),
+    }
+}
+
+fn error_to_string(e: &ScError) -> String {
+    match e {
+        ScError::Contract(code) => format!("Error(Contract, #{code})"),
+        ScError::WasmVm(code) => format!("Error(WasmVm, {})", code.name()),
+        ScError::Context(code) => format!("Error(Context, {})", code.name()),
+        ScError::Storage(code) => format!("Error(Storage, {})", code.name()),
+        ScError::Object(code) => format!("Error(Object, {})", code.name()),
+        ScError::Crypto(code) => format!("Error(Crypto, {})", code.name()),
+        ScError::Events(code) => format!("Error(Events, {})", code.name()),
+        ScError::Budget(code) => format!("Error(Budget, {})", code.name()),
+        ScError::Value(code) => format!("Error(Value, {})", code.name()),
+        ScError::Auth(code) => format!("Error(Auth, {})", code.name()),
+    }
+}
+
+// decode a value to json following its contract spec type
+pub fn scval_to_json_with_spec(v: &ScVal, ty: &ScSpecTypeDef, spec: &[ScSpecEntry]) -> Value {
+    match (ty, v) {
+        (ScSpecTypeDef::Option(_), ScVal::Void) => Value::Null,
+        (ScSpecTypeDef::Option(t), _) => scval_to_json_with_spec(v, &t.value_type, spec),
+        (ScSpecTypeDef::Result(t), _) => scval_to_json_with_spec(v, &t.ok_type, spec),
+        (ScSpecTypeDef::Vec(t), ScVal::Vec(Some(items))) => Value::Array(
+            items
+                .iter()
+                .map(|item| scval_to_json_with_spec(item, &t.element_type, spec))
+                .collect(),
+        ),
+        (ScSpecTypeDef::Map(t), ScVal::Map(Some(map))) => {
+            let entries: Vec<(Value, Value)> = map
+                .iter()
+                .map(|e| {
+                    (
+                        scval_to_json_with_spec(&e.key, &t.key_type, spec),
+                        scval_to_json_with_spec(&e.val, &t.value_type, spec),
+                    )
+                })
+                .collect();
+            json_map(entries)
+        }
+        (ScSpecTypeDef::Tuple(t), ScVal::Vec(Some(items))) => Value::Array(
+            items
+                .iter()
+                .zip(t.value_types.iter())
+                .map(|(item, ty)| scval_to_json_with_spec(item, ty, spec))
+                .collect(),
+        ),
+        (ScSpecTypeDef::Udt(t), _) => match spec::find_udt(spec, &t.name.to_string()) {
+            Some(udt) => udt_to_json(v, udt, spec),
+            None => scval_to_json(v),
+        },
+        _ => scval_to_json(v),
+    }
+}
+
+fn udt_to_json(v: &ScVal, udt: &ScSpecEntry, spec: &[ScSpecEntry]) -> Value {
+    match (udt, v) {
+        // named fields are encoded as a symbol keyed map
+        (ScSpecEntry::UdtStructV0(s), ScVal::Map(Some(map))) => {
+            let mut obj = Map::new();
+            for e in map.iter() {
+                let name = scval_as_string(&e.key).unwrap_or_default();
+                let val = match s.fields.iter().find(|f| f.name.to_string() == name) {
+                    Some(field) => scval_to_json_with_spec(&e.val, &field.type_, spec),
+                    None => scval_to_json(&e.val),
+                };
+                obj.insert(name, val);
             }
+            Value::Object(obj)
         }
-        ScVal::U128(parts) => {
-            if parts.hi == 0 {
-                Some(parts.lo)
-            } else {
-                None
+        // tuple structs are encoded as a vec
+        (ScSpecEntry::UdtStructV0(s), ScVal::Vec(Some(items))) => Value::Array(
+            s.fields
+                .iter()
+                .zip(items.iter())
+                .map(|(field, item)| scval_to_json_with_spec(item, &field.type_, spec))
+                .collect(),
+        ),
+        // unions are encoded as vec[symbol(case), values..]
+        (ScSpecEntry::UdtUnionV0(u), ScVal::Vec(Some(items))) => {
+            let Some(case_name) = items.first().and_then(scval_as_string) else {
+                return scval_to_json(v);
+            };
+            let types = u.cases.iter().find_map(|case| match case {
+                ScSpecUdtUnionCaseV0::TupleV0(t) if t.name.to_string() == case_name => {
+                    Some(&t.type_)
+                }
+                _ => None,
+            });
+            match types {
+                Some(types) => {
+                    let values = items
+                        .iter()
+                        .skip(1)
+                        .zip(types.iter())
+                        .map(|(item, ty)| scval_to_json_with_spec(item, ty, spec))
+                        .collect();
+                    let mut obj = Map::new();
+                    obj.insert(case_name, Value::Array(values));
+                    Value::Object(obj)
+                }
+                None => Value::String(case_name),
             }
         }
-        _ => None,
+        (ScSpecEntry::UdtEnumV0(e), ScVal::U32(n)) => e
+            .cases
+            .iter()
+            .find(|case| case.value == *n)
+            .map(|case| Value::String(case.name.to_string()))
+            .unwrap_or_else(|| scval_to_json(v)),
+        (ScSpecEntry::UdtErrorEnumV0(e), ScVal::Error(ScError::Contract(n))) => e
+            .cases
+            .iter()
+            .find(|case| case.value == *n)
+            .map(|case| Value::String(case.name.to_string()))
+            .unwrap_or_else(|| scval_to_json(v)),
+        _ => scval_to_json(v),
+    }
+}
+
+// maps with string keys become objects, other maps a list of [key, value]
+fn json_map(entries: Vec<(Value, Value)>) -> Value {
+    if entries.iter().all(|(k, _)| k.is_string()) {
+        let mut obj = Map::new();
+        for (k, v) in entries {
+            if let Value::String(k) = k {
+                obj.insert(k, v);
+            }
+        }
+        Value::Object(obj)
+    } else {
+        Value::Array(
+            entries
+                .into_iter()
+                .map(|(k, v)| Value::Array(vec![k, v]))
+                .collect(),
+        )
+    }
+}
+
+// decode a value without type information, integers that do not fit
+// in a json number are kept as decimal strings
+pub fn scval_to_json(v: &ScVal) -> Value {
+    match v {
+        ScVal::Void => Value::Null,
+        ScVal::Bool(b) => Value::Bool(*b),
+        ScVal::U32(n) => Value::from(*n),
+        ScVal::I32(n) => Value::from(*n),
+        ScVal::U64(n) => Value::from(*n),
+        ScVal::I64(n) => Value::from(*n),
+        ScVal::Timepoint(t) => Value::from(t.0),
+        ScVal::Duration(d) => Value::from(d.0),
+        ScVal::U128(_) | ScVal::I128(_) | ScVal::U256(_) | ScVal::I256(_) => {
+            Value::String(scval_to_string(v))
+        }
+        ScVal::Symbol(_) | ScVal::String(_) => {
+            Value::String(scval_as_string(v).unwrap_or_default())
+        }
+        ScVal::Address(a) => Value::String(a.to_string()),
+        ScVal::Bytes(b) => Value::String(hex::encode(b.as_slice())),
+        ScVal::Vec(Some(items)) => Value::Array(items.iter().map(scval_to_json).collect()),
+        ScVal::Map(Some(map)) => json_map(
+            map.iter()
+                .map(|e| (scval_to_json(&e.key), scval_to_json(&e.val)))
+                .collect(),
+        ),
+        ScVal::Vec(None) | ScVal::Map(None) => Value::Null,
+        ScVal::ContractInstance(instance) => {
+            let mut obj = Map::new();
+            obj.insert(
+                "executable".into(),
+                Value::String(match &instance.executable {
+                    ContractExecutable::Wasm(hash) => hex::encode(hash.0),
+                    ContractExecutable::StellarAsset => "stellar_asset".into(),
+                }),
+            );
+            let storage = match &instance.storage {
+                Some(map) => json_map(
+                    map.iter()
+                        .map(|e| (scval_to_json(&e.key), scval_to_json(&e.val)))
+                        .collect(),
+                ),
+                None => Value::Null,
+            };
+            obj.insert("storage".into(), storage);
+            Value::Object(obj)
+        }
+        ScVal::Error(_) | ScVal::LedgerKeyContractInstance | ScVal::LedgerKeyNonce(_) => {
+            Value::String(scval_to_string(v))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_client::xdr::{
+        Hash, Int128Parts, Int256Parts, ScContractInstance, ScErrorCode, ScMap, ScMapEntry,
+        ScNonceKey, ScSymbol, ScVec,
+    };
+
+    fn i128_parts(hi: i64, lo: u64) -> ScVal {
+        ScVal::I128(Int128Parts { hi, lo })
+    }
+
+    fn symbol(s: &str) -> ScVal {
+        ScVal::Symbol(ScSymbol(s.try_into().unwrap()))
+    }
+
+    #[test]
+    fn test_negative_i128() {
+        assert_eq!(scval_as_i128(&i128_parts(-1, u64::MAX)), Some(-1));
+        let min = i128_parts(i64::MIN, 0);
+        assert_eq!(scval_as_i128(&min), Some(i128::MIN));
+        assert_eq!(scval_to_string(&min), i128::MIN.to_string());
+        // hi=-1 with a zero low word
+        assert_eq!(scval_as_i128(&i128_parts(-1, 0)), Some(-(1 << 64)));
+        assert_eq!(scval_as_u128(&i128_parts(-1, u64::MAX)), None);
+        assert_eq!(scval_as_u64(&i128_parts(-1, u64::MAX)), None);
+    }
+
+    #[test]
+    fn test_negative_i256() {
+        let minus_one = ScVal::I256(Int256Parts {
+            hi_hi: -1,
+            hi_lo: u64::MAX,
+            lo_hi: u64::MAX,
+            lo_lo: u64::MAX,
+        });
+        assert_eq!(scval_as_i256(&minus_one), Some(I256::from(-1)));
+        assert_eq!(scval_to_string(&minus_one), "-1");
+        assert_eq!(scval_as_u256(&minus_one), None);
+
+        let min = ScVal::I256(Int256Parts {
+            hi_hi: i64::MIN,
+            hi_lo: 0,
+            lo_hi: 0,
+            lo_lo: 0,
+        });
+        assert_eq!(scval_as_i256(&min), Some(I256::MIN));
+        assert_eq!(
+            scval_as_i256(&i128_parts(i64::MIN, 0)),
+            Some(I256::from(i128::MIN))
+        );
+    }
+
+    #[test]
+    fn test_to_string() {
+        let items = ScVec(vec![ScVal::U32(1), symbol("a")].try_into().unwrap());
+        assert_eq!(scval_to_string(&ScVal::Vec(Some(items))), "[1, a]");
+        assert_eq!(scval_to_string(&ScVal::Vec(None)), "()");
+
+        let map = ScMap(
+            vec![ScMapEntry {
+                key: symbol("amount"),
+                val: i128_parts(-1, u64::MAX),
+            }]
+            .try_into()
+            .unwrap(),
+        );
+        assert_eq!(scval_to_string(&ScVal::Map(Some(map))), "{amount: -1}");
+
+        let instance = ScVal::ContractInstance(ScContractInstance {
+            executable: ContractExecutable::Wasm(Hash([0xab; 32])),
+            storage: None,
+        });
+        assert_eq!(
+            scval_to_string(&instance),
+            format!("instance(wasm {})", "ab".repeat(32))
+        );
+
+        assert_eq!(
+            scval_to_string(&ScVal::Error(ScError::Contract(7))),
+            "Error(Contract, #7)"
+        );
+        assert_eq!(
+            scval_to_string(&ScVal::Error(ScError::Auth(ScErrorCode::InvalidAction))),
+            "Error(Auth, InvalidAction)"
+        );
+        assert_eq!(
+            scval_to_string(&ScVal::LedgerKeyNonce(ScNonceKey { nonce: 3 })),
+            "nonce(3)"
+        );
     }
 }
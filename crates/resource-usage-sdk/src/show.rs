@@ -1,5 +1,17 @@
+use crate::auth::CredentialKind;
+use crate::capacity::{FunctionCapacity, LedgerCapacity, WorkloadCapacity};
+use crate::fee::FeeEstimate;
+use crate::footprint::{EntryAccess, EntryChange, EntryKind, FootprintEntry};
+use crate::headroom;
+use crate::rent::RentConfig;
 use crate::rpc_server::ContractStore;
+use crate::scaling::ScalingReport;
+use crate::scval_tools;
 use crate::statistics::ResourceMetric;
+use crate::trace::TraceEvent;
+use crate::trend::{self, FunctionTrend};
+use crate::wasm::WasmReport;
+use soroban_client::xdr::{ConfigSettingEntry, ConfigSettingId, LedgerKey};
 use std::collections::HashMap;
 
 use comfy_table::{
@@ -28,14 +40,17 @@ pub struct FuncStatistics {
     pub metrics: HashMap<&'static str, MetricStatistics>,
 }
 
+// (key, limit, avg, max, min, sum)
+pub type MetricRow = (&'static str, Option<u64>, f64, u64, u64, u128);
+
 #[derive(Clone, Debug)]
 pub struct FuncTableData {
     pub func: String,
     pub times: usize,
-    pub rows: Vec<(&'static str, u64, f64, u64, u64, u128)>, // (key, limit, avg, max, min, sum)
+    pub rows: Vec<MetricRow>,
 }
 
-const METRIC_KEYS: [&'static str; 8] = [
+pub(crate) const METRIC_KEYS: [&str; 19] = [
     "cpu_insns",
     "mem_bytes",
     "entry_bytes",
@@ -44,11 +59,22 @@ const METRIC_KEYS: [&'static str; 8] = [
     "read_bytes",
     "write_bytes",
     "min_txn_bytes",
+    "args_bytes",
+    "auth_bytes",
+    "footprint_bytes",
+    "signatures_bytes",
+    "return_bytes",
+    "wasm_bytes",
+    "fee_charged",
+    "resource_fee",
+    "rent_fee",
+    "events_count",
+    "events_bytes",
 ];
 
-const METRIC_KEYS_FOR_PRINT: [&'static str; 8] = METRIC_KEYS;
+const METRIC_KEYS_FOR_PRINT: [&str; 19] = METRIC_KEYS;
 
-fn stellar_limits_config() -> HashMap<&'static str, u64> {
+pub(crate) fn stellar_limits_config() -> HashMap<&'static str, u64> {
     HashMap::from([
         ("cpu_insns", 50_000_000),
         ("mem_bytes", 10_000_000),
@@ -58,10 +84,40 @@ fn stellar_limits_config() -> HashMap<&'static str, u64> {
         ("read_bytes", 2_000_000),
         ("write_bytes", 2_000_000),
         ("min_txn_bytes", 100_000),
+        ("wasm_bytes", 131_072),
+        ("events_bytes", 16_384),
     ])
 }
 
-fn get_metric_u64(m: &ResourceMetric, key: &str) -> Option<u64> {
+// config settings overriding the per-transaction limits
+pub const TX_LIMIT_SETTINGS: [ConfigSettingId; 1] = [ConfigSettingId::ContractEventsV0];
+
+// stellar_limits_config with the limits found in `settings`
+pub(crate) fn stellar_limits_from_settings(
+    settings: &[ConfigSettingEntry],
+) -> HashMap<&'static str, u64> {
+    let mut limits = stellar_limits_config();
+    for setting in settings {
+        if let ConfigSettingEntry::ContractEventsV0(v) = setting {
+            limits.insert("events_bytes", v.tx_max_contract_events_size_bytes as u64);
+        }
+    }
+    limits
+}
+
+// ledger-wide limits used when the network settings cannot be fetched
+pub(crate) fn stellar_ledger_limits_config() -> HashMap<&'static str, u64> {
+    HashMap::from([
+        ("cpu_insns", 500_000_000),
+        ("entry_reads", 1_000),
+        ("entry_writes", 500),
+        ("read_bytes", 7_000_000),
+        ("write_bytes", 286_720),
+        ("min_txn_bytes", 266_240),
+    ])
+}
+
+pub(crate) fn get_metric_u64(m: &ResourceMetric, key: &str) -> Option<u64> {
     match key {
         "cpu_insns" => m.cpu_insns,
         "mem_bytes" => m.mem_bytes,
@@ -71,17 +127,52 @@ fn get_metric_u64(m: &ResourceMetric, key: &str) -> Option<u64> {
         "read_bytes" => m.read_bytes.map(|v| v as u64),
         "write_bytes" => m.write_bytes.map(|v| v as u64),
         "min_txn_bytes" => m.min_txn_bytes.map(|v| v as u64),
+        "args_bytes" => m.args_bytes.map(|v| v as u64),
+        "auth_bytes" => m.auth_bytes.map(|v| v as u64),
+        "footprint_bytes" => m.footprint_bytes.map(|v| v as u64),
+        "signatures_bytes" => m.signatures_bytes.map(|v| v as u64),
+        "return_bytes" => m.return_bytes.map(|v| v as u64),
+        "wasm_bytes" => m.wasm_bytes.map(|v| v as u64),
+        "fee_charged" => m.fee_charged.map(|v| v as u64),
+        "resource_fee" => m.resource_fee.map(|v| v as u64),
+        "rent_fee" => m.rent_fee.map(|v| v as u64),
+        "events_count" => m.events_count.map(|v| v as u64),
+        "events_bytes" => m.events_bytes.map(|v| v as u64),
         _ => None,
     }
 }
 
+#[cfg(feature = "sqlite")]
+pub(crate) fn set_metric_u64(m: &mut ResourceMetric, key: &str, v: u64) {
+    match key {
+        "cpu_insns" => m.cpu_insns = Some(v),
+        "mem_bytes" => m.mem_bytes = Some(v),
+        "entry_bytes" => m.entry_bytes = Some(v as usize),
+        "entry_reads" => m.entry_reads = Some(v as usize),
+        "entry_writes" => m.entry_writes = Some(v as usize),
+        "read_bytes" => m.read_bytes = Some(v as u32),
+        "write_bytes" => m.write_bytes = Some(v as u32),
+        "min_txn_bytes" => m.min_txn_bytes = Some(v as usize),
+        "args_bytes" => m.args_bytes = Some(v as usize),
+        "auth_bytes" => m.auth_bytes = Some(v as usize),
+        "footprint_bytes" => m.footprint_bytes = Some(v as usize),
+        "signatures_bytes" => m.signatures_bytes = Some(v as usize),
+        "return_bytes" => m.return_bytes = Some(v as usize),
+        "wasm_bytes" => m.wasm_bytes = Some(v as usize),
+        "fee_charged" => m.fee_charged = Some(v as i64),
+        "resource_fee" => m.resource_fee = Some(v as i64),
+        "rent_fee" => m.rent_fee = Some(v as i64),
+        "events_count" => m.events_count = Some(v as usize),
+        "events_bytes" => m.events_bytes = Some(v as usize),
+        _ => {}
+    }
+}
+
 pub fn calc_statistics(store: &ContractStore) -> ResultStatistics {
     let mut res: ResultStatistics = HashMap::new();
 
     for (contract_name, funcs) in store {
-        let contract_entry = res
-            .entry(contract_name.clone())
-            .or_insert_with(HashMap::new);
+        let contract_entry = res.entry(contract_name.clone()).or_default();
 
         for (func_name, data) in funcs {
             let times = data.len();
@@ -136,22 +227,21 @@ pub fn load_table_data(
 ) -> Vec<FuncTableData> {
     let mut res: Vec<FuncTableData> = vec![];
 
-    for (_contract, funcs) in statistics {
+    for funcs in statistics.values() {
         for (func, data) in funcs {
-            let mut rows: Vec<(&'static str, u64, f64, u64, u64, u128)> = vec![];
+            let mut rows: Vec<MetricRow> = vec![];
 
             for key in METRIC_KEYS_FOR_PRINT.iter() {
                 let Some(stat) = data.metrics.get(key) else {
                     continue;
                 };
-                let Some(limit) = limits.get(key) else {
-                    continue;
-                };
-                if *limit == 0 {
+                // metrics without a network limit are printed uncolored
+                let limit = limits.get(key).copied();
+                if limit == Some(0) {
                     continue;
                 }
 
-                rows.push((*key, *limit, stat.avg, stat.max, stat.min, stat.sum));
+                rows.push((*key, limit, stat.avg, stat.max, stat.min, stat.sum));
             }
 
             res.push(FuncTableData {
@@ -187,8 +277,11 @@ fn center(cell: Cell) -> Cell {
     cell.set_alignment(CellAlignment::Center)
 }
 
-fn format_cell_f64(value: f64, limit: u64, cursors: LimitsCursors) -> Cell {
-    let percent = (value / limit as f64) * 100.0;
+fn format_cell_f64(value: f64, limit: Option<u64>, cursors: LimitsCursors) -> Cell {
+    let percent = match limit {
+        Some(limit) => (value / limit as f64) * 100.0,
+        None => 0.0,
+    };
     let is_danger = percent > cursors.danger * 100.0;
     let is_error = percent > cursors.error * 100.0;
 
@@ -201,8 +294,11 @@ fn format_cell_f64(value: f64, limit: u64, cursors: LimitsCursors) -> Cell {
     cell
 }
 
-fn format_cell_u64(value: u64, limit: u64, cursors: LimitsCursors) -> Cell {
-    let percent = (value as f64 / limit as f64) * 100.0;
+fn format_cell_u64(value: u64, limit: Option<u64>, cursors: LimitsCursors) -> Cell {
+    let percent = match limit {
+        Some(limit) => (value as f64 / limit as f64) * 100.0,
+        None => 0.0,
+    };
     let is_danger = percent > cursors.danger * 100.0;
     let is_error = percent > cursors.error * 100.0;
 
@@ -215,15 +311,30 @@ fn format_cell_u64(value: u64, limit: u64, cursors: LimitsCursors) -> Cell {
     cell
 }
 
+// resource table of every contract of a live or loaded store
+pub fn print_store(store: &ContractStore) {
+    for contract_id in store.keys() {
+        print_table(contract_id, store);
+    }
+}
+
 pub fn print_table(contract_id: &str, store: &ContractStore) {
+    print_table_with_limits(contract_id, store, &stellar_limits_config());
+}
+
+pub(crate) fn print_table_with_limits(
+    contract_id: &str,
+    store: &ContractStore,
+    limits: &HashMap<&'static str, u64>,
+) {
     let cursors = LimitsCursors {
         danger: 0.8,
         error: 1.0,
     };
 
-    let limits = stellar_limits_config();
-    let statistics = calc_statistics(store);
-    let mut funcs = load_table_data(&statistics, &limits);
+    let mut statistics = calc_statistics(store);
+    statistics.retain(|contract, _| contract == contract_id);
+    let mut funcs = load_table_data(&statistics, limits);
 
     funcs.sort_by(|a, b| a.func.cmp(&b.func));
 
@@ -287,7 +398,7 @@ pub fn print_table(contract_id: &str, store: &ContractStore) {
         for (key, limit, avg, max, min, sum) in f.rows {
             table.add_row(vec![
                 cyan_bold(key),
-                Cell::new(limit.to_string()),
+                Cell::new(limit.map(|v| v.to_string()).unwrap_or("-".into())),
                 format_cell_f64(avg, limit, cursors),
                 format_cell_u64(max, limit, cursors),
                 format_cell_u64(min, limit, cursors),
@@ -299,6 +410,988 @@ pub fn print_table(contract_id: &str, store: &ContractStore) {
     println!("{table}");
 }
 
+pub fn print_headroom(
+    contract_id: &str,
+    store: &ContractStore,
+    tx_limits: &HashMap<&'static str, u64>,
+    ledger: &LedgerCapacity,
+) {
+    let rows = headroom::headroom(contract_id, store, tx_limits, ledger);
+    if rows.is_empty() {
+        return;
+    }
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+
+    table.add_row(vec![
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+        center(cyan_bold("Limit Headroom")),
+        Cell::new(""),
+        Cell::new(""),
+    ]);
+
+    table.add_row(vec![
+        cyan_bold("Contract"),
+        Cell::new(""),
+        Cell::new(contract_id),
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+    ]);
+
+    table.add_row(vec![
+        cyan_bold("Function"),
+        cyan_bold("Binding limit"),
+        cyan_bold("Max usage"),
+        cyan_bold("Headroom"),
+        cyan_bold("Calls per ledger"),
+        cyan_bold("Ledger bound"),
+    ]);
+
+    for h in rows {
+        let headroom = format!("{:.1}%", h.headroom());
+        let headroom = if h.usage >= 1.0 {
+            red_bold(headroom)
+        } else if h.usage >= 0.8 {
+            yellow_bold(headroom)
+        } else {
+            Cell::new(headroom)
+        };
+        table.add_row(vec![
+            cyan_bold(h.function),
+            Cell::new(h.binding),
+            Cell::new(format!("{:.1}%", h.usage * 100.0)),
+            headroom,
+            Cell::new(h.per_ledger.map_or("-".to_string(), |n| n.to_string())),
+            Cell::new(h.ledger_binding.unwrap_or("-")),
+        ]);
+    }
+
+    println!("{table}");
+}
+
+pub fn print_capacity(
+    functions: &[FunctionCapacity],
+    ledger: &LedgerCapacity,
+    workload: Option<&WorkloadCapacity>,
+) {
+    if functions.is_empty() {
+        return;
+    }
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+
+    table.add_row(vec![
+        Cell::new(""),
+        Cell::new(""),
+        center(cyan_bold("Ledger Capacity")),
+        Cell::new(""),
+        Cell::new(""),
+    ]);
+
+    table.add_row(vec![
+        cyan_bold("Ledger"),
+        Cell::new(""),
+        Cell::new(format!(
+            "close time {:.1}s, {} cluster(s), max {} txs",
+            ledger.close_time.as_secs_f64(),
+            ledger.clusters,
+            ledger
+                .max_tx_count
+                .map_or("-".to_string(), |n| n.to_string())
+        )),
+        Cell::new(""),
+        Cell::new(""),
+    ]);
+
+    table.add_row(vec![
+        cyan_bold("Contract"),
+        cyan_bold("Function"),
+        cyan_bold("Calls per ledger"),
+        cyan_bold("Calls per second"),
+        cyan_bold("Bound by"),
+    ]);
+
+    for f in functions {
+        table.add_row(vec![
+            Cell::new(&f.contract),
+            cyan_bold(&f.function),
+            Cell::new(format!("{:.0}", f.per_ledger.floor())),
+            Cell::new(format!("{:.1}", f.per_second)),
+            Cell::new(f.binding),
+        ]);
+    }
+
+    if let Some(w) = workload {
+        table.add_row(vec![
+            cyan_bold("Mixed workload"),
+            cyan_bold("Share"),
+            cyan_bold("Calls per ledger"),
+            cyan_bold("Calls per second"),
+            Cell::new(""),
+        ]);
+        for (_, function, share, per_ledger) in &w.calls {
+            table.add_row(vec![
+                Cell::new(function),
+                Cell::new(format!("{:.1}%", share * 100.0)),
+                Cell::new(format!("{:.0}", per_ledger.floor())),
+                Cell::new(format!("{:.1}", ledger.per_second(*per_ledger))),
+                Cell::new(""),
+            ]);
+        }
+        table.add_row(vec![
+            cyan_bold("Total"),
+            Cell::new("100%"),
+            Cell::new(format!("{:.0}", w.per_ledger.floor())),
+            Cell::new(format!("{:.1}", w.per_second)),
+            Cell::new(w.binding),
+        ]);
+    }
+
+    println!("{table}");
+}
+
+#[derive(Clone, Debug)]
+pub struct FootprintRow {
+    pub kind: EntryKind,
+    pub access: EntryAccess,
+    pub owner: String,
+    pub key: String,
+    pub change: EntryChange,
+    pub size_before: Option<usize>,
+    pub size_after: Option<usize>,
+    pub times: usize,
+}
+
+// merge the footprint of every sample of a function, one row per ledger key
+pub fn load_footprint_data(samples: &[ResourceMetric]) -> Vec<FootprintRow> {
+    let mut rows: Vec<FootprintRow> = vec![];
+    let mut index: HashMap<(EntryAccess, LedgerKey), usize> = HashMap::new();
+
+    for sample in samples {
+        for entry in sample.footprint.iter() {
+            let id = (entry.access, entry.key.clone());
+            let Some(i) = index.get(&id).copied() else {
+                index.insert(id, rows.len());
+                rows.push(FootprintRow {
+                    kind: entry.kind,
+                    access: entry.access,
+                    owner: entry.owner.clone().unwrap_or_default(),
+                    key: entry
+                        .key_val
+                        .as_ref()
+                        .map(scval_tools::scval_to_string)
+                        .unwrap_or_default(),
+                    change: entry.change,
+                    size_before: entry.size_before,
+                    size_after: entry.size_after,
+                    times: 1,
+                });
+                continue;
+            };
+            let row = &mut rows[i];
+            row.change = entry.change;
+            row.size_before = row.size_before.max(entry.size_before);
+            row.size_after = row.size_after.max(entry.size_after);
+            row.times += 1;
+        }
+    }
+
+    // biggest entries first
+    rows.sort_by(|a, b| {
+        let a_size = a.size_before.max(a.size_after);
+        let b_size = b.size_before.max(b.size_after);
+        b_size.cmp(&a_size)
+    });
+    rows
+}
+
+fn format_size(size: Option<usize>) -> Cell {
+    match size {
+        Some(v) => Cell::new(v.to_string()),
+        None => Cell::new("-"),
+    }
+}
+
+pub fn print_footprint(contract_id: &str, store: &ContractStore) {
+    let Some(funcs) = store.get(contract_id) else {
+        return;
+    };
+    let mut func_names: Vec<&String> = funcs.keys().collect();
+    func_names.sort();
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+
+    table.add_row(vec![
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+        center(cyan_bold("Footprint Table")),
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+    ]);
+
+    table.add_row(vec![
+        cyan_bold("Contract"),
+        Cell::new(""),
+        Cell::new(contract_id),
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+    ]);
+
+    for func in func_names {
+        table.add_row(vec![
+            cyan_bold("Function"),
+            Cell::new(func),
+            Cell::new(""),
+            Cell::new(""),
+            Cell::new(""),
+            Cell::new(""),
+            Cell::new(""),
+            Cell::new(""),
+        ]);
+
+        table.add_row(vec![
+            cyan_bold("Type"),
+            cyan_bold("Access"),
+            cyan_bold("Owner"),
+            cyan_bold("Key"),
+            cyan_bold("Change"),
+            cyan_bold("Before"),
+            cyan_bold("After"),
+            cyan_bold("Times"),
+        ]);
+
+        for row in load_footprint_data(&funcs[func]) {
+            table.add_row(vec![
+                cyan_bold(row.kind.as_str()),
+                Cell::new(row.access.as_str()),
+                Cell::new(row.owner),
+                Cell::new(row.key),
+                Cell::new(row.change.as_str()),
+                format_size(row.size_before),
+                format_size(row.size_after),
+                Cell::new(row.times.to_string()),
+            ]);
+        }
+    }
+
+    println!("{table}");
+}
+
+// (topic, count) of the contract events emitted by a function
+pub fn load_event_topics(samples: &[ResourceMetric]) -> Vec<(String, usize)> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for sample in samples {
+        for topic in sample.event_topics.iter() {
+            *counts.entry(topic.as_str()).or_default() += 1;
+        }
+    }
+    let mut res: Vec<(String, usize)> = counts
+        .into_iter()
+        .map(|(topic, count)| (topic.to_string(), count))
+        .collect();
+    res.sort();
+    res
+}
+
+pub fn print_events(contract_id: &str, store: &ContractStore) {
+    let Some(funcs) = store.get(contract_id) else {
+        return;
+    };
+    let mut func_names: Vec<&String> = funcs
+        .iter()
+        .filter(|(_, samples)| samples.iter().any(|s| !s.event_topics.is_empty()))
+        .map(|(func, _)| func)
+        .collect();
+    if func_names.is_empty() {
+        return;
+    }
+    func_names.sort();
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+
+    table.add_row(vec![
+        Cell::new(""),
+        center(cyan_bold("Event Topics Table")),
+        Cell::new(""),
+        Cell::new(""),
+    ]);
+
+    table.add_row(vec![
+        cyan_bold("Contract"),
+        Cell::new(contract_id),
+        Cell::new(""),
+        Cell::new(""),
+    ]);
+
+    table.add_row(vec![
+        cyan_bold("Function"),
+        cyan_bold("Topic"),
+        cyan_bold("Count"),
+        cyan_bold("Per Call"),
+    ]);
+
+    for func in func_names {
+        let samples = &funcs[func];
+        for (topic, count) in load_event_topics(samples) {
+            table.add_row(vec![
+                Cell::new(func),
+                Cell::new(topic),
+                Cell::new(count.to_string()),
+                Cell::new(format!("{:.2}", count as f64 / samples.len() as f64)),
+            ]);
+        }
+    }
+
+    println!("{table}");
+}
+
+#[derive(Clone, Debug)]
+pub struct AuthRow {
+    pub credential: CredentialKind,
+    pub entries: usize,
+    pub addresses: usize,
+    pub signatures: usize,
+    pub invocations: usize,
+    pub max_depth: usize,
+    pub bytes: usize,
+    pub max_bytes: usize,
+}
+
+// auth entries of a function grouped by credential type
+pub fn load_auth_data(samples: &[ResourceMetric]) -> Vec<AuthRow> {
+    let mut rows: HashMap<CredentialKind, (AuthRow, Vec<&str>)> = HashMap::new();
+    for sample in samples {
+        for entry in sample.auth.iter() {
+            let (row, addresses) = rows.entry(entry.credential).or_insert_with(|| {
+                (
+                    AuthRow {
+                        credential: entry.credential,
+                        entries: 0,
+                        addresses: 0,
+                        signatures: 0,
+                        invocations: 0,
+                        max_depth: 0,
+                        bytes: 0,
+                        max_bytes: 0,
+                    },
+                    vec![],
+                )
+            });
+            row.entries += 1;
+            row.signatures += entry.signatures;
+            row.invocations += entry.invocations;
+            row.max_depth = row.max_depth.max(entry.depth);
+            row.bytes += entry.bytes;
+            row.max_bytes = row.max_bytes.max(entry.bytes);
+            if let Some(address) = entry.address.as_deref() {
+                if !addresses.contains(&address) {
+                    addresses.push(address);
+                }
+            }
+        }
+    }
+    let mut res: Vec<AuthRow> = rows
+        .into_values()
+        .map(|(mut row, addresses)| {
+            row.addresses = addresses.len();
+            row
+        })
+        .collect();
+    res.sort_by_key(|row| row.credential);
+    res
+}
+
+pub fn print_auth(contract_id: &str, store: &ContractStore) {
+    let Some(funcs) = store.get(contract_id) else {
+        return;
+    };
+    let mut func_names: Vec<&String> = funcs
+        .iter()
+        .filter(|(_, samples)| samples.iter().any(|s| !s.auth.is_empty()))
+        .map(|(func, _)| func)
+        .collect();
+    if func_names.is_empty() {
+        return;
+    }
+    func_names.sort();
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+
+    table.add_row(vec![
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+        center(cyan_bold("Authorization Table")),
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+    ]);
+
+    table.add_row(vec![
+        cyan_bold("Contract"),
+        Cell::new(""),
+        Cell::new(contract_id),
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+    ]);
+
+    table.add_row(vec![
+        cyan_bold("Function"),
+        cyan_bold("Credential"),
+        cyan_bold("Per Call"),
+        cyan_bold("Addresses"),
+        cyan_bold("Signatures"),
+        cyan_bold("Invocations"),
+        cyan_bold("Max Depth"),
+        cyan_bold("Avg Bytes"),
+        cyan_bold("Max Bytes"),
+    ]);
+
+    for func in func_names {
+        let samples = &funcs[func];
+        for row in load_auth_data(samples) {
+            let entries = row.entries as f64;
+            table.add_row(vec![
+                Cell::new(func),
+                Cell::new(row.credential.as_str()),
+                Cell::new(format!("{:.2}", entries / samples.len() as f64)),
+                Cell::new(row.addresses.to_string()),
+                Cell::new(format!("{:.2}", row.signatures as f64 / entries)),
+                Cell::new(format!("{:.2}", row.invocations as f64 / entries)),
+                Cell::new(row.max_depth.to_string()),
+                Cell::new(format!("{:.2}", row.bytes as f64 / entries)),
+                Cell::new(row.max_bytes.to_string()),
+            ]);
+        }
+    }
+
+    println!("{table}");
+}
+
+#[derive(Clone, Debug)]
+pub struct StorageRow {
+    pub kind: EntryKind,
+    pub key: String,
+    pub samples: usize,
+    pub first_size: usize,
+    pub last_size: usize,
+    pub max_size: usize,
+    pub ttl_extensions: usize,
+    pub live_until: Option<u32>,
+    pub last_ledger: Option<u32>,
+}
+
+fn is_contract_data(kind: EntryKind) -> bool {
+    matches!(
+        kind,
+        EntryKind::ContractInstance | EntryKind::PersistentData | EntryKind::TemporaryData
+    )
+}
+
+// size history of every contract data entry, grouped by owner contract
+pub fn load_storage_data(store: &ContractStore) -> HashMap<String, Vec<StorageRow>> {
+    let mut entries: Vec<(Option<u32>, &FootprintEntry)> = vec![];
+    for funcs in store.values() {
+        for samples in funcs.values() {
+            for sample in samples {
+                for entry in sample.footprint.iter() {
+                    if is_contract_data(entry.kind) {
+                        entries.push((sample.ledger, entry));
+                    }
+                }
+            }
+        }
+    }
+    // replay the changes in ledger order
+    entries.sort_by_key(|(ledger, _)| *ledger);
+
+    let mut res: HashMap<String, Vec<StorageRow>> = HashMap::new();
+    let mut index: HashMap<LedgerKey, usize> = HashMap::new();
+    for (ledger, entry) in entries {
+        let Some(size) = entry.size_after.or(entry.size_before) else {
+            continue;
+        };
+        let owner = entry.owner.clone().unwrap_or_default();
+        let rows = res.entry(owner).or_default();
+        let Some(i) = index.get(&entry.key).copied() else {
+            index.insert(entry.key.clone(), rows.len());
+            rows.push(StorageRow {
+                kind: entry.kind,
+                key: entry
+                    .key_val
+                    .as_ref()
+                    .map(scval_tools::scval_to_string)
+                    .unwrap_or_default(),
+                samples: 1,
+                first_size: entry.size_before.unwrap_or(size),
+                last_size: size,
+                max_size: size,
+                ttl_extensions: entry.ttl_extended() as usize,
+                live_until: entry.live_until_after.or(entry.live_until_before),
+                last_ledger: ledger,
+            });
+            continue;
+        };
+        let row = &mut rows[i];
+        row.samples += 1;
+        row.last_size = size;
+        row.max_size = row.max_size.max(size);
+        row.ttl_extensions += entry.ttl_extended() as usize;
+        row.live_until = entry
+            .live_until_after
+            .or(entry.live_until_before)
+            .or(row.live_until);
+        row.last_ledger = ledger.or(row.last_ledger);
+    }
+    res
+}
+
+fn format_growth(first: usize, last: usize) -> Cell {
+    let growth = last as i64 - first as i64;
+    let cell = Cell::new(format!("{growth:+}"));
+    if growth > 0 {
+        cell.fg(Color::Yellow).add_attribute(Attribute::Bold)
+    } else {
+        cell
+    }
+}
+
+pub fn print_storage(store: &ContractStore, rent: Option<&RentConfig>, rent_ledgers: u32) {
+    let storage = load_storage_data(store);
+    if storage.is_empty() {
+        return;
+    }
+    let mut owners: Vec<&String> = storage.keys().collect();
+    owners.sort();
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+
+    table.add_row(vec![
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+        center(cyan_bold("Storage Growth Table")),
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+    ]);
+
+    for owner in owners {
+        table.add_row(vec![
+            cyan_bold("Contract"),
+            Cell::new(""),
+            Cell::new(owner),
+            Cell::new(""),
+            Cell::new(""),
+            Cell::new(""),
+            Cell::new(""),
+            Cell::new(""),
+            Cell::new(""),
+            Cell::new(""),
+        ]);
+
+        table.add_row(vec![
+            cyan_bold("Type"),
+            cyan_bold("Key"),
+            cyan_bold("Samples"),
+            cyan_bold("First"),
+            cyan_bold("Last"),
+            cyan_bold("Max"),
+            cyan_bold("Growth"),
+            cyan_bold("TTL Extended"),
+            cyan_bold("Live Until"),
+            cyan_bold(format!("Rent ({rent_ledgers} ledgers)")),
+        ]);
+
+        for row in &storage[owner] {
+            let is_persistent = row.kind != EntryKind::TemporaryData;
+            let rent_fee = match rent {
+                Some(rent) => Cell::new(
+                    rent.extend_fee(is_persistent, row.last_size as u32, rent_ledgers)
+                        .to_string(),
+                ),
+                None => Cell::new("-"),
+            };
+            let live_until = match (row.live_until, row.last_ledger) {
+                (Some(live_until), Some(ledger)) => Cell::new(format!(
+                    "{live_until} (+{})",
+                    live_until.saturating_sub(ledger)
+                )),
+                (Some(live_until), None) => Cell::new(live_until.to_string()),
+                _ => Cell::new("-"),
+            };
+            table.add_row(vec![
+                cyan_bold(row.kind.as_str()),
+                Cell::new(&row.key),
+                Cell::new(row.samples.to_string()),
+                Cell::new(row.first_size.to_string()),
+                Cell::new(row.last_size.to_string()),
+                Cell::new(row.max_size.to_string()),
+                format_growth(row.first_size, row.last_size),
+                Cell::new(row.ttl_extensions.to_string()),
+                live_until,
+                rent_fee,
+            ]);
+        }
+    }
+
+    println!("{table}");
+}
+
+pub fn print_wasm(report: &WasmReport) {
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+
+    table.add_row(vec![
+        Cell::new(""),
+        center(cyan_bold("Wasm Module Table")),
+        Cell::new(""),
+    ]);
+
+    table.add_row(vec![
+        cyan_bold("Wasm Hash"),
+        Cell::new(&report.hash),
+        Cell::new(""),
+    ]);
+    table.add_row(vec![
+        cyan_bold("Size"),
+        Cell::new(report.size.to_string()),
+        Cell::new(""),
+    ]);
+    table.add_row(vec![
+        cyan_bold("Functions"),
+        Cell::new(report.functions.to_string()),
+        Cell::new(""),
+    ]);
+    table.add_row(vec![
+        cyan_bold("Imports"),
+        Cell::new(report.imports.len().to_string()),
+        Cell::new(""),
+    ]);
+    table.add_row(vec![
+        cyan_bold("Data Segments"),
+        Cell::new(report.data_segments.to_string()),
+        Cell::new(""),
+    ]);
+
+    table.add_row(vec![
+        cyan_bold("Section"),
+        cyan_bold("Bytes"),
+        cyan_bold("Percent"),
+    ]);
+    for (name, size) in report.sections.iter() {
+        table.add_row(vec![
+            Cell::new(name),
+            Cell::new(size.to_string()),
+            Cell::new(format!(
+                "{:.2}%",
+                *size as f64 / report.size.max(1) as f64 * 100.0
+            )),
+        ]);
+    }
+
+    table.add_row(vec![
+        cyan_bold("Export"),
+        cyan_bold("Signature"),
+        Cell::new(""),
+    ]);
+    for export in report.exports.iter() {
+        table.add_row(vec![
+            Cell::new(&export.name),
+            Cell::new(export.signature.as_deref().unwrap_or("-")),
+            Cell::new(""),
+        ]);
+    }
+
+    // host functions grouped by env module
+    let mut imports: HashMap<&str, Vec<&str>> = HashMap::new();
+    for import in report.imports.iter() {
+        imports
+            .entry(import.module_name())
+            .or_default()
+            .push(import.name.as_str());
+    }
+    let mut modules: Vec<&str> = imports.keys().copied().collect();
+    modules.sort();
+    table.add_row(vec![
+        cyan_bold("Host Module"),
+        cyan_bold("Imports"),
+        cyan_bold("Count"),
+    ]);
+    for module in modules {
+        let names = &imports[module];
+        table.add_row(vec![
+            Cell::new(module),
+            Cell::new(names.join(" ")),
+            Cell::new(names.len().to_string()),
+        ]);
+    }
+
+    println!("{table}");
+}
+
+pub fn print_trace(tx_hash: &str, events: &[TraceEvent]) {
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+
+    table.add_row(vec![
+        Cell::new(""),
+        Cell::new(""),
+        center(cyan_bold("Diagnostic Event Trace")),
+        Cell::new(""),
+        Cell::new(""),
+    ]);
+
+    table.add_row(vec![
+        cyan_bold("Transaction"),
+        Cell::new(""),
+        Cell::new(tx_hash),
+        Cell::new(""),
+        Cell::new(""),
+    ]);
+
+    table.add_row(vec![
+        cyan_bold("#"),
+        cyan_bold("Kind"),
+        cyan_bold("Contract"),
+        cyan_bold("Topics"),
+        cyan_bold("Data"),
+    ]);
+
+    for event in events {
+        let mut kind = Cell::new(&event.kind);
+        if event.kind == "error" || !event.in_successful_contract_call {
+            kind = kind.fg(Color::Red).add_attribute(Attribute::Bold);
+        }
+        table.add_row(vec![
+            Cell::new(event.index.to_string()),
+            kind,
+            Cell::new(event.contract.as_deref().unwrap_or("-")),
+            Cell::new(event.topics_string()),
+            Cell::new(event.data_string()),
+        ]);
+    }
+
+    println!("{table}");
+}
+
+pub fn print_scaling(report: &ScalingReport) {
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+
+    table.add_row(vec![
+        Cell::new(""),
+        Cell::new(""),
+        center(cyan_bold(format!(
+            "{} scaling by {}",
+            report.function, report.arg
+        ))),
+        Cell::new(""),
+        Cell::new(""),
+    ]);
+
+    table.add_row(vec![
+        cyan_bold("Contract"),
+        Cell::new(""),
+        Cell::new(&report.contract),
+        Cell::new(""),
+        Cell::new(""),
+    ]);
+
+    table.add_row(vec![
+        cyan_bold("Samples"),
+        Cell::new(""),
+        Cell::new(format!(
+            "{} ({} = {}..{})",
+            report.samples, report.arg, report.range.0, report.range.1
+        )),
+        Cell::new(""),
+        Cell::new(""),
+    ]);
+
+    table.add_row(vec![
+        cyan_bold("Metric"),
+        cyan_bold("Model"),
+        cyan_bold("R²"),
+        cyan_bold("Limit"),
+        cyan_bold("Max safe input"),
+    ]);
+
+    for m in &report.metrics {
+        let max_safe = match (m.limit, m.max_safe) {
+            (None, _) => Cell::new("-"),
+            (Some(_), None) => Cell::new("unbounded"),
+            (Some(_), Some(x)) if (x as f64) < report.range.1 => red_bold(x.to_string()),
+            (Some(_), Some(x)) if (x as f64) < report.range.1 * 2.0 => yellow_bold(x.to_string()),
+            (Some(_), Some(x)) => Cell::new(x.to_string()),
+        };
+        table.add_row(vec![
+            Cell::new(m.metric),
+            Cell::new(m.fit.formula()),
+            Cell::new(format!("{:.3}", m.fit.r2)),
+            Cell::new(m.limit.map_or("-".to_string(), |l| l.to_string())),
+            max_safe,
+        ]);
+    }
+
+    println!("{table}");
+}
+
+// fee components of each variant, changes relative to the first one
+pub fn print_fees(estimates: &[(String, FeeEstimate)]) {
+    if estimates.is_empty() {
+        return;
+    }
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+
+    let mut header = vec![cyan_bold("Fee (stroops)")];
+    header.extend(estimates.iter().map(|(label, _)| cyan_bold(label)));
+    table.add_row(header);
+
+    let columns: Vec<Vec<(&str, i64)>> = estimates
+        .iter()
+        .map(|(_, e)| {
+            let mut rows = e.components().to_vec();
+            rows.push(("non_refundable", e.non_refundable()));
+            rows.push(("refundable", e.refundable()));
+            rows.push(("total", e.total()));
+            rows
+        })
+        .collect();
+    for (i, &(name, base)) in columns[0].iter().enumerate() {
+        let mut row = if i >= columns[0].len() - 3 {
+            vec![cyan_bold(name)]
+        } else {
+            vec![Cell::new(name)]
+        };
+        for (j, column) in columns.iter().enumerate() {
+            let v = column[i].1;
+            let cell = if j == 0 || v == base {
+                Cell::new(v.to_string())
+            } else if base == 0 {
+                yellow_bold(v.to_string())
+            } else {
+                let change = (v - base) as f64 / base as f64 * 100.0;
+                let text = format!("{v} ({change:+.1}%)");
+                if change > 0.0 {
+                    red_bold(text)
+                } else {
+                    Cell::new(text).fg(Color::Green)
+                }
+            };
+            row.push(cell);
+        }
+        table.add_row(row);
+    }
+
+    println!("{table}");
+}
+
+pub fn print_trend(trend: &FunctionTrend) {
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+
+    table.add_row(vec![
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+        center(cyan_bold(format!("{} Trend", trend.function))),
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+    ]);
+
+    table.add_row(vec![
+        cyan_bold("Contract"),
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(&trend.contract),
+        Cell::new(""),
+        Cell::new(""),
+        Cell::new(""),
+    ]);
+
+    table.add_row(vec![
+        cyan_bold("Metric"),
+        cyan_bold("Avg"),
+        cyan_bold("Max"),
+        cyan_bold("First avg"),
+        cyan_bold("Last avg"),
+        cyan_bold("Change"),
+        cyan_bold("Jump at"),
+    ]);
+
+    for m in &trend.metrics {
+        let (Some(first), Some(last)) = (m.points.first(), m.points.last()) else {
+            continue;
+        };
+        let avgs: Vec<f64> = m.points.iter().map(|p| p.avg).collect();
+        let maxes: Vec<f64> = m.points.iter().map(|p| p.max as f64).collect();
+        let change = if first.avg == 0.0 {
+            Cell::new("-")
+        } else {
+            let percent = (last.avg - first.avg) / first.avg * 100.0;
+            let cell = Cell::new(format!("{percent:+.2}%"));
+            if percent > 10.0 {
+                cell.fg(Color::Red).add_attribute(Attribute::Bold)
+            } else {
+                cell
+            }
+        };
+        let jump = match m.jump {
+            Some(i) => red_bold(&m.points[i].label),
+            None => Cell::new("-"),
+        };
+        table.add_row(vec![
+            Cell::new(m.metric),
+            Cell::new(trend::sparkline(&avgs)),
+            Cell::new(trend::sparkline(&maxes)),
+            Cell::new(format!("{:.2}", first.avg)),
+            Cell::new(format!("{:.2}", last.avg)),
+            change,
+            jump,
+        ]);
+    }
+
+    println!("{table}");
+}
+
 // use crate::rpc_server::FunctionStore;
 // #[test]
 // fn test() {
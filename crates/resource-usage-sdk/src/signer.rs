@@ -0,0 +1,367 @@
+use soroban_client::{
+    hashing::{HashingBehavior, Sha256Hasher},
+    keypair::{Keypair, KeypairBehavior},
+    transaction::{Transaction, TransactionBehavior},
+    xdr::{
+        DecoratedSignature, Hash, HashIdPreimage, HashIdPreimageSorobanAuthorization, Limits,
+        ScBytes, ScMap, ScMapEntry, ScSymbol, ScVal, ScVec, Signature, SignatureHint,
+        SorobanAuthorizationEntry, SorobanCredentials, WriteXdr,
+    },
+};
+use std::collections::HashMap;
+
+use crate::Error;
+
+// an ed25519 key able to sign for a stellar account
+pub trait Signer {
+    // G... strkey of the signing key
+    fn public_key(&self) -> String;
+    fn sign(&self, payload: &[u8]) -> Result<Vec<u8>, Error>;
+}
+
+impl Signer for Keypair {
+    fn public_key(&self) -> String {
+        KeypairBehavior::public_key(self)
+    }
+
+    fn sign(&self, payload: &[u8]) -> Result<Vec<u8>, Error> {
+        KeypairBehavior::sign(self, payload).map_err(|e| Error::SignerError(e.to_string()))
+    }
+}
+
+pub type SignFn = Box<dyn Fn(&[u8]) -> Result<Vec<u8>, Error> + Send + Sync>;
+
+// signs through a callback, e.g. a hardware wallet or a remote service
+pub struct ExternalSigner {
+    public_key: String,
+    sign_fn: SignFn,
+}
+
+impl ExternalSigner {
+    pub fn new(public_key: &str, sign_fn: SignFn) -> Self {
+        Self {
+            public_key: public_key.to_string(),
+            sign_fn,
+        }
+    }
+}
+
+impl Signer for ExternalSigner {
+    fn public_key(&self) -> String {
+        self.public_key.clone()
+    }
+
+    fn sign(&self, payload: &[u8]) -> Result<Vec<u8>, Error> {
+        (self.sign_fn)(payload)
+    }
+}
+
+// signature value of a contract account (custom __check_auth) for the
+// sha256 authorization payload
+pub type ContractAuthFn = Box<dyn Fn(&[u8; 32]) -> Result<ScVal, Error> + Send + Sync>;
+
+enum Credentials {
+    Keys(Vec<Box<dyn Signer>>),
+    Contract(ContractAuthFn),
+}
+
+struct NamedAccount {
+    address: String,
+    credentials: Credentials,
+}
+
+// named accounts used by the harness to sign envelopes and auth entries
+#[derive(Default)]
+pub struct SignerRegistry {
+    accounts: HashMap<String, NamedAccount>,
+}
+
+impl SignerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // account `name` signed by `keypair`
+    pub fn add_keypair(&mut self, name: &str, keypair: Keypair) {
+        let address = Signer::public_key(&keypair);
+        self.add_account(name, &address);
+        self.add_signer(name, Box::new(keypair));
+    }
+
+    // account without signers yet, the signers of a multisig account are
+    // added with add_signer
+    pub fn add_account(&mut self, name: &str, address: &str) {
+        self.accounts
+            .entry(name.to_string())
+            .or_insert_with(|| NamedAccount {
+                address: address.to_string(),
+                credentials: Credentials::Keys(vec![]),
+            });
+    }
+
+    pub fn add_signer(&mut self, name: &str, signer: Box<dyn Signer>) {
+        let account = self
+            .accounts
+            .entry(name.to_string())
+            .or_insert_with(|| NamedAccount {
+                address: signer.public_key(),
+                credentials: Credentials::Keys(vec![]),
+            });
+        match &mut account.credentials {
+            Credentials::Keys(signers) => signers.push(signer),
+            Credentials::Contract(_) => account.credentials = Credentials::Keys(vec![signer]),
+        }
+    }
+
+    // contract account, only signs auth entries
+    pub fn add_contract(&mut self, name: &str, contract_id: &str, auth_fn: ContractAuthFn) {
+        self.accounts.insert(
+            name.to_string(),
+            NamedAccount {
+                address: contract_id.to_string(),
+                credentials: Credentials::Contract(auth_fn),
+            },
+        );
+    }
+
+    pub fn address(&self, name: &str) -> Option<&str> {
+        self.accounts.get(name).map(|a| a.address.as_str())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.accounts.contains_key(name)
+    }
+
+    fn by_address(&self, address: &str) -> Option<&NamedAccount> {
+        self.accounts.values().find(|a| a.address == address)
+    }
+
+    // add one envelope signature per signer of account `name`
+    pub fn sign_transaction(&self, tx: &mut Transaction, name: &str) -> Result<(), Error> {
+        let account = self
+            .accounts
+            .get(name)
+            .ok_or_else(|| Error::UnknownSigner(name.to_string()))?;
+        let Credentials::Keys(signers) = &account.credentials else {
+            return Err(Error::SignerError(format!(
+                "{name} is a contract account and cannot sign transactions"
+            )));
+        };
+        let tx_hash = tx.hash();
+        for signer in signers {
+            let public_key = raw_public_key(&signer.public_key())?;
+            let signature = signer.sign(&tx_hash)?;
+            let mut hint = [0u8; 4];
+            hint.copy_from_slice(&public_key[28..]);
+            tx.signatures.push(DecoratedSignature {
+                hint: SignatureHint(hint),
+                signature: Signature(signature.try_into()?),
+            });
+        }
+        tx.hash = Some(tx_hash);
+        Ok(())
+    }
+
+    // sign the address credentials of registered accounts, other entries
+    // are returned unchanged
+    pub fn sign_auth_entries(
+        &self,
+        entries: &[SorobanAuthorizationEntry],
+        network_passphrase: &str,
+        valid_until_ledger: u32,
+    ) -> Result<Vec<SorobanAuthorizationEntry>, Error> {
+        let network_id = Hash(Sha256Hasher::hash(network_passphrase.as_bytes()));
+        entries
+            .iter()
+            .map(|entry| {
+                let SorobanCredentials::Address(creds) = &entry.credentials else {
+                    return Ok(entry.clone());
+                };
+                let Some(account) = self.by_address(&creds.address.to_string()) else {
+                    return Ok(entry.clone());
+                };
+                let preimage =
+                    HashIdPreimage::SorobanAuthorization(HashIdPreimageSorobanAuthorization {
+                        network_id: network_id.clone(),
+                        nonce: creds.nonce,
+                        signature_expiration_ledger: valid_until_ledger,
+                        invocation: entry.root_invocation.clone(),
+                    });
+                let payload = Sha256Hasher::hash(preimage.to_xdr(Limits::none())?);
+                let signature = match &account.credentials {
+                    Credentials::Keys(signers) => account_signature(signers, &payload)?,
+                    Credentials::Contract(auth_fn) => auth_fn(&payload)?,
+                };
+                let mut signed = entry.clone();
+                if let SorobanCredentials::Address(creds) = &mut signed.credentials {
+                    creds.signature_expiration_ledger = valid_until_ledger;
+                    creds.signature = signature;
+                }
+                Ok(signed)
+            })
+            .collect()
+    }
+}
+
+// vec of {public_key, signature} maps sorted by public key, as expected by
+// the stellar account __check_auth
+fn account_signature(signers: &[Box<dyn Signer>], payload: &[u8; 32]) -> Result<ScVal, Error> {
+    let mut sigs = vec![];
+    for signer in signers {
+        let public_key = raw_public_key(&signer.public_key())?;
+        let signature = signer.sign(payload)?;
+        sigs.push((public_key, signature));
+    }
+    sigs.sort_by_key(|a| a.0);
+
+    let mut vals = vec![];
+    for (public_key, signature) in sigs {
+        let entries = vec![
+            ScMapEntry {
+                key: ScVal::Symbol(ScSymbol("public_key".try_into()?)),
+                val: ScVal::Bytes(ScBytes(public_key.to_vec().try_into()?)),
+            },
+            ScMapEntry {
+                key: ScVal::Symbol(ScSymbol("signature".try_into()?)),
+                val: ScVal::Bytes(ScBytes(signature.try_into()?)),
+            },
+        ];
+        vals.push(ScVal::Map(Some(ScMap(entries.try_into()?))));
+    }
+    Ok(ScVal::Vec(Some(ScVec(vals.try_into()?))))
+}
+
+fn raw_public_key(public_key: &str) -> Result<[u8; 32], Error> {
+    stellar_strkey::ed25519::PublicKey::from_string(public_key)
+        .map(|pk| pk.0)
+        .map_err(|_| Error::InvalidArgument(public_key.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::test::CONTRACT;
+    use soroban_client::xdr::{
+        InvokeContractArgs, ScAddress, SorobanAddressCredentials, SorobanAuthorizedFunction,
+        SorobanAuthorizedInvocation,
+    };
+    use std::str::FromStr;
+
+    const PASSPHRASE: &str = "Test SDF Network ; September 2015";
+
+    fn keypairs() -> Vec<Keypair> {
+        let mut keypairs: Vec<_> = (1..=3u8)
+            .map(|i| Keypair::from_raw_ed25519_seed(&[i; 32]).unwrap())
+            .collect();
+        // signers registered out of public key order
+        keypairs.sort_by_key(|k| std::cmp::Reverse(k.raw_public_key().clone()));
+        keypairs
+    }
+
+    fn auth_entry(address: &str) -> SorobanAuthorizationEntry {
+        let contract = stellar_strkey::Contract(CONTRACT).to_string();
+        SorobanAuthorizationEntry {
+            credentials: SorobanCredentials::Address(SorobanAddressCredentials {
+                address: ScAddress::from_str(address).unwrap(),
+                nonce: 42,
+                signature_expiration_ledger: 0,
+                signature: ScVal::Void,
+            }),
+            root_invocation: SorobanAuthorizedInvocation {
+                function: SorobanAuthorizedFunction::ContractFn(InvokeContractArgs {
+                    contract_address: ScAddress::from_str(&contract).unwrap(),
+                    function_name: ScSymbol("transfer".try_into().unwrap()),
+                    args: vec![ScVal::U64(5)].try_into().unwrap(),
+                }),
+                sub_invocations: vec![].try_into().unwrap(),
+            },
+        }
+    }
+
+    // (public_key, signature) of each {public_key, signature} map
+    fn signatures(val: &ScVal) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let ScVal::Vec(Some(items)) = val else {
+            panic!("not a vec: {val:?}");
+        };
+        items
+            .iter()
+            .map(|item| {
+                let ScVal::Map(Some(map)) = item else {
+                    panic!("not a map: {item:?}");
+                };
+                let bytes = |i: usize, name: &str| {
+                    assert_eq!(
+                        map[i].key,
+                        ScVal::Symbol(ScSymbol(name.try_into().unwrap()))
+                    );
+                    let ScVal::Bytes(b) = &map[i].val else {
+                        panic!("not bytes: {:?}", map[i].val);
+                    };
+                    b.to_vec()
+                };
+                assert_eq!(map.len(), 2);
+                (bytes(0, "public_key"), bytes(1, "signature"))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_account_signature() {
+        let keypairs = keypairs();
+        let payload = [9u8; 32];
+        let signers: Vec<Box<dyn Signer>> = keypairs
+            .iter()
+            .map(|k| Box::new(k.clone()) as Box<dyn Signer>)
+            .collect();
+        let sigs = signatures(&account_signature(&signers, &payload).unwrap());
+
+        assert_eq!(sigs.len(), 3);
+        assert!(sigs.windows(2).all(|w| w[0].0 < w[1].0));
+        for (public_key, signature) in sigs {
+            let keypair = keypairs
+                .iter()
+                .find(|k| *k.raw_public_key() == public_key)
+                .unwrap();
+            assert!(keypair.verify(&payload, &signature));
+        }
+    }
+
+    #[test]
+    fn test_sign_auth_entries() {
+        let keypairs = keypairs();
+        let mut registry = SignerRegistry::new();
+        let address = Signer::public_key(&keypairs[0]);
+        registry.add_account("multisig", &address);
+        for keypair in &keypairs {
+            registry.add_signer("multisig", Box::new(keypair.clone()));
+        }
+        let other = Signer::public_key(&keypairs[1]);
+        let entries = vec![auth_entry(&address), auth_entry(&other)];
+
+        let signed = registry
+            .sign_auth_entries(&entries, PASSPHRASE, 1234)
+            .unwrap();
+
+        // unregistered address left unchanged
+        assert_eq!(signed[1], entries[1]);
+        let SorobanCredentials::Address(creds) = &signed[0].credentials else {
+            panic!("not address credentials");
+        };
+        assert_eq!(creds.signature_expiration_ledger, 1234);
+        assert_eq!(creds.nonce, 42);
+
+        let preimage = HashIdPreimage::SorobanAuthorization(HashIdPreimageSorobanAuthorization {
+            network_id: Hash(Sha256Hasher::hash(PASSPHRASE.as_bytes())),
+            nonce: 42,
+            signature_expiration_ledger: 1234,
+            invocation: entries[0].root_invocation.clone(),
+        });
+        let payload = Sha256Hasher::hash(preimage.to_xdr(Limits::none()).unwrap());
+        let sigs = signatures(&creds.signature);
+        assert_eq!(sigs.len(), 3);
+        for (keypair, (public_key, signature)) in keypairs.iter().rev().zip(sigs) {
+            assert_eq!(*keypair.raw_public_key(), public_key);
+            assert!(keypair.verify(&payload, &signature));
+        }
+    }
+}
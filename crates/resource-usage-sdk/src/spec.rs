@@ -0,0 +1,93 @@
+use std::io::Cursor;
+
+use soroban_client::xdr::{Limited, Limits, ReadXdr, ScSpecEntry, ScSpecFunctionV0, ScSpecTypeDef};
+
+use crate::Error;
+
+// custom section holding the xdr encoded contract spec
+pub const SPEC_SECTION: &str = "contractspecv0";
+
+// decode the concatenated ScSpecEntry of a contractspecv0 section
+pub fn parse_spec(data: &[u8]) -> Result<Vec<ScSpecEntry>, Error> {
+    let mut reader = Limited::new(Cursor::new(data), Limits::none());
+    let entries = ScSpecEntry::read_xdr_iter(&mut reader).collect::<Result<Vec<_>, _>>()?;
+    Ok(entries)
+}
+
+pub fn find_function<'a>(spec: &'a [ScSpecEntry], name: &str) -> Option<&'a ScSpecFunctionV0> {
+    spec.iter().find_map(|entry| match entry {
+        ScSpecEntry::FunctionV0(f) if f.name.0.to_string() == name => Some(f),
+        _ => None,
+    })
+}
+
+// struct, union, enum or error enum definition named `name`
+pub fn find_udt<'a>(spec: &'a [ScSpecEntry], name: &str) -> Option<&'a ScSpecEntry> {
+    spec.iter().find(|entry| {
+        let udt_name = match entry {
+            ScSpecEntry::UdtStructV0(s) => &s.name,
+            ScSpecEntry::UdtUnionV0(u) => &u.name,
+            ScSpecEntry::UdtEnumV0(e) => &e.name,
+            ScSpecEntry::UdtErrorEnumV0(e) => &e.name,
+            _ => return false,
+        };
+        udt_name.to_string() == name
+    })
+}
+
+// e.g. "transfer(from: Address, to: Address, amount: i128)"
+pub fn function_signature(f: &ScSpecFunctionV0) -> String {
+    let inputs: Vec<String> = f
+        .inputs
+        .iter()
+        .map(|input| format!("{}: {}", input.name, type_name(&input.type_)))
+        .collect();
+    let mut signature = format!("{}({})", f.name.0, inputs.join(", "));
+    if let Some(output) = f.outputs.first() {
+        signature.push_str(" -> ");
+        signature.push_str(&type_name(output));
+    }
+    signature
+}
+
+pub fn type_name(def: &ScSpecTypeDef) -> String {
+    match def {
+        ScSpecTypeDef::Val => "Val".into(),
+        ScSpecTypeDef::Bool => "bool".into(),
+        ScSpecTypeDef::Void => "()".into(),
+        ScSpecTypeDef::Error => "Error".into(),
+        ScSpecTypeDef::U32 => "u32".into(),
+        ScSpecTypeDef::I32 => "i32".into(),
+        ScSpecTypeDef::U64 => "u64".into(),
+        ScSpecTypeDef::I64 => "i64".into(),
+        ScSpecTypeDef::Timepoint => "Timepoint".into(),
+        ScSpecTypeDef::Duration => "Duration".into(),
+        ScSpecTypeDef::U128 => "u128".into(),
+        ScSpecTypeDef::I128 => "i128".into(),
+        ScSpecTypeDef::U256 => "U256".into(),
+        ScSpecTypeDef::I256 => "I256".into(),
+        ScSpecTypeDef::Bytes => "Bytes".into(),
+        ScSpecTypeDef::String => "String".into(),
+        ScSpecTypeDef::Symbol => "Symbol".into(),
+        ScSpecTypeDef::Address => "Address".into(),
+        ScSpecTypeDef::MuxedAddress => "MuxedAddress".into(),
+        ScSpecTypeDef::Option(t) => format!("Option<{}>", type_name(&t.value_type)),
+        ScSpecTypeDef::Result(t) => format!(
+            "Result<{}, {}>",
+            type_name(&t.ok_type),
+            type_name(&t.error_type)
+        ),
+        ScSpecTypeDef::Vec(t) => format!("Vec<{}>", type_name(&t.element_type)),
+        ScSpecTypeDef::Map(t) => format!(
+            "Map<{}, {}>",
+            type_name(&t.key_type),
+            type_name(&t.value_type)
+        ),
+        ScSpecTypeDef::Tuple(t) => {
+            let types: Vec<String> = t.value_types.iter().map(type_name).collect();
+            format!("({})", types.join(", "))
+        }
+        ScSpecTypeDef::BytesN(t) => format!("BytesN<{}>", t.n),
+        ScSpecTypeDef::Udt(t) => t.name.to_string(),
+    }
+}
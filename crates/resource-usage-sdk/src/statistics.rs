@@ -1,13 +1,22 @@
 use crate::Error;
 use std::collections::HashMap;
 
-use crate::{rpc_server::ContractStore, scval_tools};
+use crate::{
+    auth::{self, AuthEntryInfo},
+    footprint::{self, FootprintEntry},
+    rpc_server::ContractStore,
+    scval_tools,
+    trace::{self, TraceEvent},
+};
 use soroban_client::{
-    soroban_rpc::{GetTransactionResponse, SimulateTransactionResponse},
+    hashing::{HashingBehavior, Sha256Hasher},
+    soroban_rpc::{SimulateTransactionResponse, TransactionDetails},
     transaction::Transaction,
     xdr::{
-        ContractEventBody, HostFunction, LedgerEntryChange, Limits, OperationBody, ScAddress,
-        TransactionMeta, TransactionMetaV4, WriteXdr,
+        ContractEventBody, ContractIdPreimage, FeeBumpTransactionInnerTx, Hash, HashIdPreimage,
+        HashIdPreimageContractId, HostFunction, LedgerEntryChange, Limits, OperationBody,
+        ScAddress, ScVal, SorobanTransactionMetaExt, TransactionEnvelope, TransactionExt,
+        TransactionMeta, TransactionMetaV4, TransactionV1Envelope, WriteXdr,
     },
 };
 
@@ -22,6 +31,31 @@ pub struct ResourceMetric {
     pub read_bytes: Option<u32>,
     pub write_bytes: Option<u32>,
     pub min_txn_bytes: Option<usize>,
+    // breakdown of min_txn_bytes
+    pub args_bytes: Option<usize>,
+    pub auth_bytes: Option<usize>,
+    pub footprint_bytes: Option<usize>,
+    pub signatures_bytes: Option<usize>,
+    pub return_bytes: Option<usize>,
+    // uploaded wasm size, only for wasm uploads
+    pub wasm_bytes: Option<usize>,
+    // fees in stroops
+    pub fee_charged: Option<i64>,
+    pub resource_fee: Option<i64>,
+    pub rent_fee: Option<i64>,
+    pub events_count: Option<usize>,
+    // contract events plus the return value, both count toward the
+    // tx_max_contract_events_size_bytes limit
+    pub events_bytes: Option<usize>,
+    // symbol/string topics of each contract event, e.g. "transfer"
+    pub event_topics: Vec<String>,
+    // arguments of the invoked contract function
+    pub args: Vec<ScVal>,
+    pub auth: Vec<AuthEntryInfo>,
+    pub footprint: Vec<FootprintEntry>,
+    pub diagnostic_events: Vec<TraceEvent>,
+    pub ledger: Option<u32>,
+    pub tx_hash: Option<String>,
 }
 
 // xdr safe parameter
@@ -33,7 +67,7 @@ const LIMITS: Limits = Limits {
 // statistic simulate and transaction usage
 pub fn handle_transaction(
     sim_tx: &SimulateTransactionResponse,
-    tx_result: &GetTransactionResponse,
+    tx_result: &TransactionDetails,
 ) -> Result<ResourceMetric, Error> {
     let (meta, _) = tx_result.to_result_meta().ok_or(Error::MissingMeta)?;
     match meta {
@@ -48,7 +82,7 @@ pub fn handle_transaction(
 // meta v4 support
 pub fn handle_meta_v4(
     sim_tx: &SimulateTransactionResponse,
-    tx_result: &GetTransactionResponse,
+    tx_result: &TransactionDetails,
     meta: &TransactionMetaV4,
 ) -> Result<ResourceMetric, Error> {
     let Some(sim_transaction) = sim_tx.to_transaction_data() else {
@@ -56,14 +90,43 @@ pub fn handle_meta_v4(
     };
     let resource = sim_transaction.resources;
     let footprint = resource.footprint;
+    let footprint_entries = footprint::footprint_entries(&footprint, meta, LIMITS.clone());
     let entry_reads = footprint.read_only.len();
     let entry_writes = footprint.read_write.len();
     let read_bytes = resource.disk_read_bytes;
     let write_bytes = resource.write_bytes;
-    let min_txn_bytes = tx_result.to_envelope().to_xdr(LIMITS.clone())?.len();
-    let entry_bytes = max_entry_value_len(&meta, LIMITS.clone());
-    let metrics = get_core_metrics(&meta);
-    return Ok(ResourceMetric {
+    let envelope = tx_result.to_envelope();
+    let min_txn_bytes = envelope.to_xdr(LIMITS.clone())?.len();
+    let envelope_sizes = envelope
+        .as_ref()
+        .map(|e| get_envelope_sizes(e, LIMITS.clone()))
+        .unwrap_or_default();
+    let fee_charged = tx_result.to_result().map(|r| r.fee_charged);
+    let (resource_fee, rent_fee) = match meta.soroban_meta.as_ref().map(|m| &m.ext) {
+        Some(SorobanTransactionMetaExt::V1(ext)) => (
+            Some(
+                ext.total_non_refundable_resource_fee_charged
+                    + ext.total_refundable_resource_fee_charged,
+            ),
+            Some(ext.rent_fee_charged),
+        ),
+        _ => (None, None),
+    };
+    let auth_entries = envelope
+        .as_ref()
+        .map(|e| get_auth_entries(e, LIMITS.clone()))
+        .unwrap_or_default();
+    let args = envelope.as_ref().map(get_invoke_args).unwrap_or_default();
+    let return_bytes = meta
+        .soroban_meta
+        .as_ref()
+        .and_then(|m| m.return_value.as_ref())
+        .and_then(|v| v.to_xdr(LIMITS.clone()).ok())
+        .map(|b| b.len());
+    let entry_bytes = max_entry_value_len(meta, LIMITS.clone());
+    let metrics = get_core_metrics(meta);
+    let events = get_contract_events(meta, LIMITS.clone());
+    Ok(ResourceMetric {
         cpu_insns: metrics.cpu_insn,
         mem_bytes: metrics.mem_byte,
         entry_bytes: Some(entry_bytes),
@@ -72,7 +135,25 @@ pub fn handle_meta_v4(
         read_bytes: Some(read_bytes),
         write_bytes: Some(write_bytes),
         min_txn_bytes: Some(min_txn_bytes),
-    });
+        args_bytes: Some(envelope_sizes.args),
+        auth_bytes: Some(envelope_sizes.auth),
+        footprint_bytes: Some(envelope_sizes.footprint),
+        signatures_bytes: Some(envelope_sizes.signatures),
+        return_bytes,
+        wasm_bytes: (envelope_sizes.wasm > 0).then_some(envelope_sizes.wasm),
+        fee_charged,
+        resource_fee,
+        rent_fee,
+        events_count: Some(events.count),
+        events_bytes: Some(events.bytes + return_bytes.unwrap_or(0)),
+        event_topics: events.topics,
+        args,
+        auth: auth_entries,
+        footprint: footprint_entries,
+        diagnostic_events: trace::trace_events(&meta.diagnostic_events),
+        ledger: tx_result.ledger,
+        tx_hash: None,
+    })
 }
 
 // find out max len in operation-change
@@ -98,6 +179,119 @@ fn max_entry_value_len(meta: &TransactionMetaV4, limits: Limits) -> usize {
     max_len
 }
 
+#[derive(Default)]
+struct EnvelopeSizes {
+    args: usize,
+    auth: usize,
+    footprint: usize,
+    signatures: usize,
+    wasm: usize,
+}
+
+// split the envelope size into invoke args, auth entries, footprint and signatures
+fn get_envelope_sizes(envelope: &TransactionEnvelope, limits: Limits) -> EnvelopeSizes {
+    let mut sizes = EnvelopeSizes::default();
+    if let Some(v1) = inner_v1(envelope) {
+        add_v1_sizes(&mut sizes, v1, &limits);
+    }
+    if let TransactionEnvelope::TxFeeBump(fee_bump) = envelope {
+        sizes.signatures += xdr_len(&fee_bump.signatures, &limits);
+    }
+    sizes
+}
+
+// the v1 transaction, unwrapping fee bumps
+fn inner_v1(envelope: &TransactionEnvelope) -> Option<&TransactionV1Envelope> {
+    match envelope {
+        TransactionEnvelope::Tx(v1) => Some(v1),
+        TransactionEnvelope::TxFeeBump(fee_bump) => {
+            let FeeBumpTransactionInnerTx::Tx(inner) = &fee_bump.tx.inner_tx;
+            Some(inner)
+        }
+        TransactionEnvelope::TxV0(_) => None,
+    }
+}
+
+// arguments of the first invoke contract operation
+fn get_invoke_args(envelope: &TransactionEnvelope) -> Vec<ScVal> {
+    let Some(v1) = inner_v1(envelope) else {
+        return vec![];
+    };
+    v1.tx
+        .operations
+        .iter()
+        .find_map(|operation| match &operation.body {
+            OperationBody::InvokeHostFunction(invoke_op) => match &invoke_op.host_function {
+                HostFunction::InvokeContract(args) => Some(args.args.to_vec()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+// decode the auth entries of every invoke host function operation
+fn get_auth_entries(envelope: &TransactionEnvelope, limits: Limits) -> Vec<AuthEntryInfo> {
+    let Some(v1) = inner_v1(envelope) else {
+        return vec![];
+    };
+    let mut res = vec![];
+    for operation in v1.tx.operations.iter() {
+        if let OperationBody::InvokeHostFunction(invoke_op) = &operation.body {
+            res.extend(auth::auth_entries(&invoke_op.auth, limits.clone()));
+        }
+    }
+    res
+}
+
+fn add_v1_sizes(sizes: &mut EnvelopeSizes, v1: &TransactionV1Envelope, limits: &Limits) {
+    sizes.signatures += xdr_len(&v1.signatures, limits);
+    if let TransactionExt::V1(data) = &v1.tx.ext {
+        sizes.footprint += xdr_len(&data.resources.footprint, limits);
+    }
+    for operation in v1.tx.operations.iter() {
+        let OperationBody::InvokeHostFunction(invoke_op) = &operation.body else {
+            continue;
+        };
+        sizes.auth += xdr_len(&invoke_op.auth, limits);
+        match &invoke_op.host_function {
+            HostFunction::InvokeContract(args) => sizes.args += xdr_len(&args.args, limits),
+            HostFunction::UploadContractWasm(wasm) => sizes.wasm += wasm.len(),
+            _ => {}
+        }
+    }
+}
+
+fn xdr_len<T: WriteXdr>(v: &T, limits: &Limits) -> usize {
+    v.to_xdr(limits.clone()).map(|b| b.len()).unwrap_or(0)
+}
+
+#[derive(Default)]
+struct Events {
+    count: usize,
+    bytes: usize,
+    topics: Vec<String>,
+}
+
+// contract events emitted by the operations, sized as the host does (xdr len)
+fn get_contract_events(meta: &TransactionMetaV4, limits: Limits) -> Events {
+    let mut events = Events::default();
+    for op in meta.operations.iter() {
+        for event in op.events.iter() {
+            events.count += 1;
+            events.bytes += xdr_len(event, &limits);
+            let ContractEventBody::V0(body) = &event.body;
+            let topics: Vec<String> = body
+                .topics
+                .iter()
+                .filter_map(scval_tools::scval_as_string)
+                .collect();
+            events.topics.push(topics.join(":"));
+        }
+    }
+    events
+}
+
 #[derive(Default)]
 struct Metrics {
     cpu_insn: Option<u64>,
@@ -117,9 +311,7 @@ const CORE_KEYS: [&str; 4] = [
 fn get_core_metrics(meta: &TransactionMetaV4) -> Metrics {
     let mut map: HashMap<&'static str, u64> = HashMap::new();
     for te in meta.diagnostic_events.iter() {
-        let body = match &te.event.body {
-            ContractEventBody::V0(v0) => v0,
-        };
+        let ContractEventBody::V0(body) = &te.event.body;
         let mut is_core_metrics = false;
         let mut matched_key: Option<&'static str> = None;
         for topic in body.topics.iter().filter_map(scval_tools::scval_as_string) {
@@ -152,36 +344,86 @@ fn get_core_metrics(meta: &TransactionMetaV4) -> Metrics {
     }
 }
 
-// store transation usage stats
+// synthetic contract id grouping wasm uploads and contract creations
+pub const DEPLOY_SECTION: &str = "deploy";
+
+// store transation usage stats under each (contract, function) of `keys`
 pub fn store_transaction(
     store_stats: &mut ContractStore,
-    transaction: &Transaction,
+    keys: &[(String, String)],
     stats: &ResourceMetric,
 ) {
+    for (str_key, function_name) in keys.iter().cloned() {
+        // Rust: stored_stats[contract_id][func_name].push(stats)
+        store_stats
+            .entry(str_key)
+            .or_default()
+            .entry(function_name)
+            .or_default()
+            .push(stats.clone());
+    }
+}
+
+// (contract, function) of every host function of the transaction
+pub fn transaction_keys(transaction: &Transaction) -> Vec<(String, String)> {
+    let mut res = vec![];
     let Some(operations) = &transaction.operations else {
-        return;
+        return res;
     };
     for operation in operations.iter() {
         let invoke_op = match &operation.body {
             OperationBody::InvokeHostFunction(invoke_op) => invoke_op,
             _ => continue,
         };
-        let args = match &invoke_op.host_function {
-            HostFunction::InvokeContract(invoke_contract) => invoke_contract,
-            _ => continue,
-        };
-        let contract_id = match &args.contract_address {
-            ScAddress::Contract(contract) => contract,
-            _ => continue,
+        let key = match &invoke_op.host_function {
+            HostFunction::InvokeContract(args) => {
+                let contract_id = match &args.contract_address {
+                    ScAddress::Contract(contract) => contract,
+                    _ => continue,
+                };
+                let str_key = stellar_strkey::Contract(contract_id.as_ref().0);
+                (str_key.to_string(), args.function_name.0.to_string())
+            }
+            HostFunction::UploadContractWasm(wasm) => (
+                DEPLOY_SECTION.to_string(),
+                format!("upload_wasm:{}", hex::encode(Sha256Hasher::hash(wasm))),
+            ),
+            HostFunction::CreateContract(args) => (
+                DEPLOY_SECTION.to_string(),
+                format!(
+                    "create_contract:{}",
+                    contract_id(&transaction.network_passphrase, &args.contract_id_preimage)
+                ),
+            ),
+            HostFunction::CreateContractV2(args) => (
+                DEPLOY_SECTION.to_string(),
+                format!(
+                    "create_contract:{}",
+                    contract_id(&transaction.network_passphrase, &args.contract_id_preimage)
+                ),
+            ),
         };
-        let str_key = stellar_strkey::Contract(contract_id.as_ref().0);
-        let function_name = &args.function_name.0.to_string();
-        // Rust: stored_stats[contract_id][func_name].push(stats)
-        store_stats
-            .entry(str_key.to_string())
-            .or_default()
-            .entry(function_name.clone())
-            .or_default()
-            .push(stats.clone());
+        res.push(key);
     }
+    res
+}
+
+// keys of the RestoreFootprint transaction submitted for `transaction`,
+// one "<function>:restore" line item next to each of its functions
+pub fn restore_keys(transaction: &Transaction) -> Vec<(String, String)> {
+    transaction_keys(transaction)
+        .into_iter()
+        .map(|(contract, function)| (contract, format!("{function}:restore")))
+        .collect()
+}
+
+// address of the contract created from `preimage` on the given network
+pub(crate) fn contract_id(network_passphrase: &str, preimage: &ContractIdPreimage) -> String {
+    let network_id = Sha256Hasher::hash(network_passphrase.as_bytes());
+    let preimage = HashIdPreimage::ContractId(HashIdPreimageContractId {
+        network_id: Hash(network_id),
+        contract_id_preimage: preimage.clone(),
+    });
+    let xdr = preimage.to_xdr(LIMITS.clone()).unwrap_or_default();
+    stellar_strkey::Contract(Sha256Hasher::hash(xdr)).to_string()
 }
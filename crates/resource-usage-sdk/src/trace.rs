@@ -0,0 +1,84 @@
+use serde_json::{json, Value};
+use soroban_client::xdr::{ContractEventBody, DiagnosticEvent, ScVal};
+
+use crate::scval_tools;
+
+// first topic of the host emitted diagnostic events
+const DIAGNOSTIC_KINDS: [&str; 5] = ["fn_call", "fn_return", "log", "error", "core_metrics"];
+
+// one decoded diagnostic event
+#[derive(Debug, Clone)]
+pub struct TraceEvent {
+    pub index: usize,
+    // fn_call, fn_return, log, error, core_metrics or the event type
+    pub kind: String,
+    pub event_type: &'static str,
+    pub contract: Option<String>,
+    pub in_successful_contract_call: bool,
+    pub topics: Vec<ScVal>,
+    pub data: ScVal,
+}
+
+impl TraceEvent {
+    pub fn topics_string(&self) -> String {
+        let topics: Vec<String> = self
+            .topics
+            .iter()
+            .map(scval_tools::scval_to_string)
+            .collect();
+        format!("[{}]", topics.join(", "))
+    }
+
+    pub fn data_string(&self) -> String {
+        scval_tools::scval_to_string(&self.data)
+    }
+
+    pub fn to_json(&self, tx_hash: Option<&str>) -> Value {
+        json!({
+            "tx_hash": tx_hash,
+            "index": self.index,
+            "kind": self.kind,
+            "type": self.event_type,
+            "contract": self.contract,
+            "in_successful_contract_call": self.in_successful_contract_call,
+            "topics": self.topics.iter().map(scval_tools::scval_to_json).collect::<Vec<_>>(),
+            "data": scval_tools::scval_to_json(&self.data),
+        })
+    }
+}
+
+pub fn trace_events(events: &[DiagnosticEvent]) -> Vec<TraceEvent> {
+    events
+        .iter()
+        .enumerate()
+        .map(|(index, de)| {
+            let ContractEventBody::V0(body) = &de.event.body;
+            let event_type = de.event.type_.name();
+            let kind = body
+                .topics
+                .first()
+                .and_then(scval_tools::scval_as_string)
+                .filter(|topic| DIAGNOSTIC_KINDS.contains(&topic.as_str()))
+                .unwrap_or_else(|| event_type.to_lowercase());
+            TraceEvent {
+                index,
+                kind,
+                event_type,
+                contract: de.event.contract_id.as_ref().map(|id| id.to_string()),
+                in_successful_contract_call: de.in_successful_contract_call,
+                topics: body.topics.to_vec(),
+                data: body.data.clone(),
+            }
+        })
+        .collect()
+}
+
+// one json object per line, in emission order
+pub fn to_json_lines(tx_hash: Option<&str>, events: &[TraceEvent]) -> String {
+    let mut res = String::new();
+    for event in events {
+        res.push_str(&event.to_json(tx_hash).to_string());
+        res.push('\n');
+    }
+    res
+}
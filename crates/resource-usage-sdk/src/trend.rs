@@ -0,0 +1,270 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::{rpc_server::ContractStore, show, Error};
+
+// relative increase of the average between two runs flagged as a jump
+const JUMP_THRESHOLD: f64 = 0.10;
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+#[derive(Debug, Clone)]
+pub struct TrendPoint {
+    // short commit, or run id when the commit is unknown
+    pub label: String,
+    pub avg: f64,
+    pub max: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct MetricTrend {
+    pub metric: &'static str,
+    pub points: Vec<TrendPoint>,
+    // index of the point with the largest avg jump over the previous one
+    pub jump: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FunctionTrend {
+    pub contract: String,
+    pub function: String,
+    pub metrics: Vec<MetricTrend>,
+}
+
+// one calc_statistics per run, runs ordered from the oldest
+pub fn trends(runs: &[(String, ContractStore)]) -> Vec<FunctionTrend> {
+    // (contract, function) -> metric -> points
+    let mut series: BTreeMap<(String, String), BTreeMap<&'static str, Vec<TrendPoint>>> =
+        BTreeMap::new();
+    for (label, store) in runs {
+        for (contract, funcs) in show::calc_statistics(store) {
+            for (function, stats) in funcs {
+                let entry = series.entry((contract.clone(), function)).or_default();
+                for (metric, stat) in stats.metrics {
+                    entry.entry(metric).or_default().push(TrendPoint {
+                        label: label.clone(),
+                        avg: stat.avg,
+                        max: stat.max,
+                    });
+                }
+            }
+        }
+    }
+
+    series
+        .into_iter()
+        .map(|((contract, function), metrics)| FunctionTrend {
+            contract,
+            function,
+            metrics: show::METRIC_KEYS
+                .iter()
+                .filter_map(|key| {
+                    let points = metrics.get(key)?.clone();
+                    let avgs: Vec<f64> = points.iter().map(|p| p.avg).collect();
+                    Some(MetricTrend {
+                        metric: key,
+                        jump: find_jump(&avgs),
+                        points,
+                    })
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+fn find_jump(values: &[f64]) -> Option<usize> {
+    values
+        .windows(2)
+        .enumerate()
+        .filter(|(_, w)| w[0] > 0.0 && (w[1] - w[0]) / w[0] > JUMP_THRESHOLD)
+        .max_by(|(_, a), (_, b)| ((a[1] - a[0]) / a[0]).total_cmp(&((b[1] - b[0]) / b[0])))
+        .map(|(i, _)| i + 1)
+}
+
+pub fn sparkline(values: &[f64]) -> String {
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    values
+        .iter()
+        .map(|v| {
+            if max <= min {
+                return SPARKS[0];
+            }
+            let level = ((v - min) / (max - min) * (SPARKS.len() - 1) as f64).round();
+            SPARKS[level as usize]
+        })
+        .collect()
+}
+
+// one svg per function with a panel of avg (solid) and max (dashed) lines
+// per metric, returns the written files
+pub fn write_svg_charts(
+    dir: impl AsRef<Path>,
+    trends: &[FunctionTrend],
+) -> Result<Vec<PathBuf>, Error> {
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir)?;
+    let mut files = vec![];
+    for trend in trends {
+        let name: String = format!("{}_{}.svg", trend.contract, trend.function)
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let path = dir.join(name);
+        std::fs::write(&path, svg_chart(trend))?;
+        files.push(path);
+    }
+    Ok(files)
+}
+
+pub fn svg_chart(trend: &FunctionTrend) -> String {
+    const WIDTH: f64 = 640.0;
+    const PANEL: f64 = 120.0;
+    const MARGIN: f64 = 40.0;
+    let height = PANEL * trend.metrics.len() as f64 + MARGIN;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{WIDTH}\" height=\"{height}\" font-family=\"monospace\" font-size=\"11\">\n\
+         <text x=\"{MARGIN}\" y=\"20\" font-size=\"14\">{} {}</text>\n",
+        xml_escape(&trend.contract),
+        xml_escape(&trend.function)
+    );
+    for (i, m) in trend.metrics.iter().enumerate() {
+        let top = MARGIN + i as f64 * PANEL;
+        let plot_h = PANEL - 40.0;
+        let plot_w = WIDTH - 2.0 * MARGIN;
+        let max = m.points.iter().map(|p| p.max as f64).fold(0.0, f64::max);
+        let scale_y = |v: f64| {
+            if max == 0.0 {
+                top + plot_h
+            } else {
+                top + plot_h - v / max * plot_h
+            }
+        };
+        let step = plot_w / (m.points.len().max(2) - 1) as f64;
+        let x = |j: usize| MARGIN + j as f64 * step;
+
+        svg.push_str(&format!(
+            "<text x=\"{MARGIN}\" y=\"{}\">{} (max {max})</text>\n",
+            top + 10.0,
+            m.metric
+        ));
+        let avg: Vec<String> = m
+            .points
+            .iter()
+            .enumerate()
+            .map(|(j, p)| format!("{:.1},{:.1}", x(j), scale_y(p.avg)))
+            .collect();
+        let maxes: Vec<String> = m
+            .points
+            .iter()
+            .enumerate()
+            .map(|(j, p)| format!("{:.1},{:.1}", x(j), scale_y(p.max as f64)))
+            .collect();
+        svg.push_str(&format!(
+            "<polyline fill=\"none\" stroke=\"#1f77b4\" stroke-width=\"2\" points=\"{}\"/>\n",
+            avg.join(" ")
+        ));
+        svg.push_str(&format!(
+            "<polyline fill=\"none\" stroke=\"#ff7f0e\" stroke-dasharray=\"4 2\" points=\"{}\"/>\n",
+            maxes.join(" ")
+        ));
+        if let Some(j) = m.jump {
+            svg.push_str(&format!(
+                "<line x1=\"{0:.1}\" x2=\"{0:.1}\" y1=\"{1:.1}\" y2=\"{2:.1}\" stroke=\"#d62728\"/>\n",
+                x(j),
+                top + 14.0,
+                top + plot_h
+            ));
+        }
+        for (j, p) in m.points.iter().enumerate() {
+            svg.push_str(&format!(
+                "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"9\" text-anchor=\"middle\">{}</text>\n",
+                x(j),
+                top + plot_h + 14.0,
+                xml_escape(&p.label)
+            ));
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn trend(points: &[(f64, u64)]) -> FunctionTrend {
+        let points = points
+            .iter()
+            .enumerate()
+            .map(|(i, &(avg, max))| TrendPoint {
+                label: format!("run{i}"),
+                avg,
+                max,
+            })
+            .collect();
+        FunctionTrend {
+            contract: "C<1>".into(),
+            function: "transfer".into(),
+            metrics: vec![MetricTrend {
+                metric: "cpu_insns",
+                points,
+                jump: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_find_jump() {
+        assert_eq!(find_jump(&[]), None);
+        assert_eq!(find_jump(&[100.0]), None);
+        // exactly 10% is not a jump
+        assert_eq!(find_jump(&[1000.0, 1100.0]), None);
+        assert_eq!(find_jump(&[1000.0, 1101.0]), Some(1));
+        // largest relative increase wins, starting from zero is ignored
+        assert_eq!(find_jump(&[0.0, 100.0, 120.0, 200.0]), Some(3));
+        assert_eq!(find_jump(&[200.0, 100.0]), None);
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[]), "");
+        assert_eq!(sparkline(&[5.0]), "▁");
+        assert_eq!(sparkline(&[3.0, 3.0]), "▁▁");
+        assert_eq!(sparkline(&[0.0, 7.0, 3.5]), "▁█▅");
+    }
+
+    #[test]
+    fn test_svg_chart() {
+        let svg = svg_chart(&trend(&[]));
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+        assert!(svg.contains("C&lt;1&gt; transfer"));
+        assert!(svg.contains("cpu_insns (max 0)"));
+        assert!(svg.contains("points=\"\""));
+        assert!(!svg.contains("NaN"));
+
+        let svg = svg_chart(&trend(&[(5.0, 10)]));
+        assert!(svg.contains("cpu_insns (max 10)"));
+        assert!(svg.contains(">run0</text>"));
+        assert!(!svg.contains("NaN") && !svg.contains("inf"));
+
+        let no_metrics = FunctionTrend {
+            metrics: vec![],
+            ..trend(&[])
+        };
+        assert!(!svg_chart(&no_metrics).contains("polyline"));
+    }
+}
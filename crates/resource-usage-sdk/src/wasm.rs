@@ -0,0 +1,145 @@
+use soroban_client::{
+    hashing::{HashingBehavior, Sha256Hasher},
+    xdr::ScSpecEntry,
+};
+use wasmparser::{ExternalKind, Parser, Payload, TypeRef};
+
+use crate::{spec, Error};
+
+#[derive(Debug, Clone)]
+pub struct HostImport {
+    pub module: String,
+    pub name: String,
+}
+
+impl HostImport {
+    // soroban env groups host functions in single letter modules
+    pub fn module_name(&self) -> &str {
+        match self.module.as_str() {
+            "a" => "address",
+            "b" => "buf",
+            "c" => "crypto",
+            "d" => "call",
+            "i" => "int",
+            "l" => "ledger",
+            "m" => "map",
+            "p" => "prng",
+            "t" => "test",
+            "v" => "vec",
+            "x" => "context",
+            other => other,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportedFunction {
+    pub name: String,
+    // signature from the contract spec, None if the export has no spec entry
+    pub signature: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WasmReport {
+    // sha256 of the module, same as the uploaded wasm hash
+    pub hash: String,
+    pub size: usize,
+    // (section name, bytes)
+    pub sections: Vec<(String, usize)>,
+    pub functions: usize,
+    pub data_segments: usize,
+    pub imports: Vec<HostImport>,
+    pub exports: Vec<ExportedFunction>,
+    pub spec: Vec<ScSpecEntry>,
+}
+
+pub fn analyze_wasm(wasm: &[u8]) -> Result<WasmReport, Error> {
+    let mut report = WasmReport {
+        hash: hex::encode(Sha256Hasher::hash(wasm)),
+        size: wasm.len(),
+        sections: vec![],
+        functions: 0,
+        data_segments: 0,
+        imports: vec![],
+        exports: vec![],
+        spec: vec![],
+    };
+    let mut export_names: Vec<String> = vec![];
+
+    for payload in Parser::new(0).parse_all(wasm) {
+        let (name, range) = match payload? {
+            Payload::TypeSection(r) => ("type".to_string(), r.range()),
+            Payload::ImportSection(r) => {
+                let range = r.range();
+                for import in r.into_imports() {
+                    let import = import?;
+                    if let TypeRef::Func(_) = import.ty {
+                        report.imports.push(HostImport {
+                            module: import.module.to_string(),
+                            name: import.name.to_string(),
+                        });
+                    }
+                }
+                ("import".to_string(), range)
+            }
+            Payload::FunctionSection(r) => {
+                report.functions = r.count() as usize;
+                ("function".to_string(), r.range())
+            }
+            Payload::TableSection(r) => ("table".to_string(), r.range()),
+            Payload::MemorySection(r) => ("memory".to_string(), r.range()),
+            Payload::GlobalSection(r) => ("global".to_string(), r.range()),
+            Payload::ExportSection(r) => {
+                let range = r.range();
+                for export in r {
+                    let export = export?;
+                    if matches!(export.kind, ExternalKind::Func | ExternalKind::FuncExact) {
+                        export_names.push(export.name.to_string());
+                    }
+                }
+                ("export".to_string(), range)
+            }
+            Payload::StartSection { range, .. } => ("start".to_string(), range),
+            Payload::ElementSection(r) => ("element".to_string(), r.range()),
+            Payload::DataCountSection { range, .. } => ("data_count".to_string(), range),
+            Payload::DataSection(r) => {
+                report.data_segments = r.count() as usize;
+                ("data".to_string(), r.range())
+            }
+            Payload::CodeSectionStart {
+                unchecked_range, ..
+            } => ("code".to_string(), unchecked_range),
+            Payload::CustomSection(r) => {
+                if r.name() == spec::SPEC_SECTION {
+                    report.spec = spec::parse_spec(r.data())?;
+                }
+                (format!("custom:{}", r.name()), r.range())
+            }
+            _ => continue,
+        };
+        report
+            .sections
+            .push((name, (range.end - range.start) as usize));
+    }
+
+    report.exports = export_names
+        .into_iter()
+        .map(|name| {
+            let signature = spec::find_function(&report.spec, &name).map(spec::function_signature);
+            ExportedFunction { name, signature }
+        })
+        .collect();
+    Ok(report)
+}
+
+// only the contract spec of a wasm module
+pub fn contract_spec(wasm: &[u8]) -> Result<Vec<ScSpecEntry>, Error> {
+    for payload in Parser::new(0).parse_all(wasm) {
+        if let Payload::CustomSection(r) = payload? {
+            if r.name() == spec::SPEC_SECTION {
+                return spec::parse_spec(r.data());
+            }
+        }
+    }
+    Ok(vec![])
+}
@@ -0,0 +1,528 @@
+use soroban_client::hashing::{HashingBehavior, Sha256Hasher};
+use soroban_client::soroban_rpc::{TransactionDetails, TransactionStatus};
+use soroban_client::xdr::{
+    Hash, Limits, TransactionEnvelope, TransactionSignaturePayload,
+    TransactionSignaturePayloadTaggedTransaction, WriteXdr,
+};
+use soroban_client::{Pagination, Server};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout, Instant};
+use tracing::field::Empty;
+use tracing::{info, info_span, warn, Span};
+
+use crate::config::NetworkConfig;
+use crate::prometheus::PrometheusExporter;
+use crate::rpc_server::{self, HashMapValue};
+use crate::statistics::{self, ResourceMetric};
+use crate::{show, Error};
+
+// transactions fetched per getTransactions page
+const PAGE_LIMIT: u32 = 200;
+// longest pause between two polls while transactions are pending
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+#[derive(Debug)]
+struct Pending {
+    value: HashMapValue,
+    // latest ledger when the transaction was sent, where the search starts
+    ledger: u32,
+    sent_at: Instant,
+}
+
+#[derive(Debug)]
+pub(crate) struct Resolved {
+    pub hash: String,
+    pub keys: Vec<(String, String)>,
+    pub stats: Result<ResourceMetric, Error>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    pending: HashMap<String, Pending>,
+    // lowest ledger of the transactions watched since the last round
+    added: Option<u32>,
+    resolved: Vec<Resolved>,
+    exporter: Option<PrometheusExporter>,
+}
+
+// polls the sent transactions in the background and collects their
+// resources as soon as they are in a ledger
+#[derive(Debug, Default)]
+pub(crate) struct Watcher {
+    state: Arc<Mutex<State>>,
+    // new pending transaction
+    wake: Arc<Notify>,
+    // end of a polling round
+    done: Arc<Notify>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        if let Some(task) = &self.task {
+            task.abort();
+        }
+    }
+}
+
+impl Watcher {
+    pub fn set_exporter(&mut self, exporter: PrometheusExporter) {
+        self.lock().exporter = Some(exporter);
+    }
+
+    // the polling task is spawned on the first watched transaction
+    pub fn watch(
+        &mut self,
+        server: &Arc<Server>,
+        config: &NetworkConfig,
+        hash: String,
+        value: HashMapValue,
+        ledger: u32,
+    ) {
+        {
+            let mut state = self.lock();
+            state.pending.insert(
+                hash,
+                Pending {
+                    value,
+                    ledger,
+                    sent_at: Instant::now(),
+                },
+            );
+            state.added = Some(state.added.map_or(ledger, |l| l.min(ledger)));
+        }
+        if self.task.is_none() {
+            self.task = Some(tokio::spawn(poll(
+                server.clone(),
+                config.clone(),
+                self.state.clone(),
+                self.wake.clone(),
+                self.done.clone(),
+            )));
+        }
+        self.wake.notify_one();
+    }
+
+    // wait until every pending transaction is resolved or timed out,
+    // returns the resolved ones in confirmation order
+    pub async fn flush(&self) -> Vec<Resolved> {
+        self.wait_for(|state| {
+            state
+                .pending
+                .is_empty()
+                .then(|| std::mem::take(&mut state.resolved))
+        })
+        .await
+    }
+
+    // wait until `hash` is resolved or timed out, true if it succeeded. its
+    // result is still returned by the next flush
+    pub async fn wait(&self, hash: &str) -> bool {
+        self.wait_for(|state| {
+            (!state.pending.contains_key(hash))
+                .then(|| state.resolved.iter().any(|r| r.hash == hash))
+        })
+        .await
+    }
+
+    async fn wait_for<T>(&self, mut ready: impl FnMut(&mut State) -> Option<T>) -> T {
+        loop {
+            let done = self.done.notified();
+            {
+                let mut state = self.lock();
+                // a panicked or aborted task never resolves the others
+                let stopped = self.task.as_ref().is_none_or(|t| t.is_finished());
+                if stopped && !state.pending.is_empty() {
+                    warn!(
+                        pending = state.pending.len(),
+                        "transaction watcher stopped, dropping pending transactions"
+                    );
+                    state.pending.clear();
+                }
+                if let Some(res) = ready(&mut state) {
+                    return res;
+                }
+            }
+            // the task may stop while waiting, check it again after a round
+            let _ = timeout(MAX_BACKOFF, done).await;
+        }
+    }
+
+    pub fn clear(&self) {
+        let mut state = self.lock();
+        state.pending.clear();
+        state.resolved.clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+async fn poll(
+    server: Arc<Server>,
+    config: NetworkConfig,
+    state: Arc<Mutex<State>>,
+    wake: Arc<Notify>,
+    done: Arc<Notify>,
+) {
+    let mut backoff = config.poll_interval;
+    // where the previous round stopped, the next one continues from there
+    let mut cursor: Option<Cursor> = None;
+    loop {
+        let (start, added) = {
+            let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+            let start = state.pending.values().map(|p| p.ledger).min();
+            (start, state.added.take())
+        };
+        // a transaction sent before the last scanned ledger may be behind
+        // the cursor already
+        if let (Some(added), Some(c)) = (added, &cursor) {
+            if added < c.ledger {
+                cursor = None;
+            }
+        }
+        let Some(start) = start else {
+            done.notify_waiters();
+            wake.notified().await;
+            backoff = config.poll_interval;
+            continue;
+        };
+
+        let page = match &cursor {
+            Some(c) => Pagination::Cursor(c.cursor.clone()),
+            None => Pagination::From(start),
+        };
+        let found = match fetch_since(&server, &config, &state, page).await {
+            Ok((found, next)) => {
+                cursor = next.or(cursor);
+                found
+            }
+            // rpc without getTransactions, or start out of its retention
+            Err(e) => {
+                warn!(error = %e, "getTransactions failed, polling each transaction");
+                cursor = None;
+                fetch_each(&server, &state).await
+            }
+        };
+        expire(&state, config.wait_time);
+        done.notify_waiters();
+
+        backoff = if found > 0 {
+            config.poll_interval
+        } else {
+            (backoff * 2).min(MAX_BACKOFF.max(config.poll_interval))
+        };
+        tokio::select! {
+            _ = sleep(backoff) => {}
+            _ = wake.notified() => backoff = config.poll_interval,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Cursor {
+    cursor: String,
+    // highest ledger scanned
+    ledger: u32,
+}
+
+// resolve the pending transactions found from `page` on, returns how many
+// were found and the cursor after the last transaction
+async fn fetch_since(
+    server: &Server,
+    config: &NetworkConfig,
+    state: &Mutex<State>,
+    mut page: Pagination,
+) -> Result<(usize, Option<Cursor>), soroban_client::error::Error> {
+    let passphrase = config.network.passphrase();
+    let mut found = 0;
+    let mut last: Option<Cursor> = None;
+    loop {
+        let response = server.get_transactions(page, PAGE_LIMIT).await?;
+        let ledger = response.transactions.iter().filter_map(|t| t.ledger).max();
+        if let (false, Some(ledger)) = (response.cursor.is_empty(), ledger) {
+            last = Some(Cursor {
+                cursor: response.cursor.clone(),
+                ledger,
+            });
+        }
+        for info in &response.transactions {
+            let Some(hash) = info
+                .to_envelope()
+                .and_then(|e| envelope_hash(&e, passphrase))
+            else {
+                continue;
+            };
+            if resolve(state, &hash, info) {
+                found += 1;
+            }
+        }
+        if response.transactions.len() < PAGE_LIMIT as usize {
+            return Ok((found, last));
+        }
+        page = Pagination::Cursor(response.cursor);
+    }
+}
+
+async fn fetch_each(server: &Server, state: &Mutex<State>) -> usize {
+    let hashes: Vec<String> = {
+        let state = state.lock().unwrap_or_else(|e| e.into_inner());
+        state.pending.keys().cloned().collect()
+    };
+    let mut found = 0;
+    for hash in hashes {
+        match server.get_transaction(&hash).await {
+            Ok(tx) if tx.status == TransactionStatus::NotFound => {}
+            Ok(tx) => {
+                if resolve(state, &hash, &tx) {
+                    found += 1;
+                }
+            }
+            Err(e) => warn!(tx_hash = %hash, error = %e, "failed to get transaction"),
+        }
+    }
+    found
+}
+
+// collect the resources of a confirmed transaction, false if `hash` is not
+// pending. the transaction stays pending while it is decoded outside the
+// lock, so flush cannot return before its result is pushed
+fn resolve(state: &Mutex<State>, hash: &str, tx: &TransactionDetails) -> bool {
+    let (value, sent_at, exporter) = {
+        let state = state.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(pending) = state.pending.get(hash) else {
+            return false;
+        };
+        (
+            pending.value.clone(),
+            pending.sent_at,
+            state.exporter.clone(),
+        )
+    };
+    if tx.status != TransactionStatus::Success {
+        warn!(tx_hash = %hash, status = ?tx.status, "transaction failed");
+        state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .pending
+            .remove(hash);
+        return true;
+    }
+
+    let span = metrics_span(hash);
+    let _enter = span.enter();
+    rpc_server::record_keys(&span, &value.keys);
+    let stats = statistics::handle_transaction(&value.sim_tx_res, tx).map(|mut stats| {
+        stats.tx_hash = Some(hash.to_string());
+        for key in show::METRIC_KEYS {
+            if let Some(value) = show::get_metric_u64(&stats, key) {
+                span.record(key, value);
+            }
+        }
+        info!(
+            ledger = stats.ledger,
+            wait_ms = sent_at.elapsed().as_millis() as u64,
+            "collected transaction resources"
+        );
+        if let Some(exporter) = &exporter {
+            for (contract, function) in &value.keys {
+                exporter.observe(contract, function, &stats);
+            }
+        }
+        stats
+    });
+    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+    state.pending.remove(hash);
+    state.resolved.push(Resolved {
+        hash: hash.to_string(),
+        keys: value.keys,
+        stats,
+    });
+    true
+}
+
+// drop the transactions not found within `wait_time`
+fn expire(state: &Mutex<State>, wait_time: Duration) {
+    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+    state.pending.retain(|hash, pending| {
+        let expired = pending.sent_at.elapsed() > wait_time;
+        if expired {
+            warn!(tx_hash = %hash, "transaction not found before the wait time");
+        }
+        !expired
+    });
+}
+
+// hex hash of a transaction envelope on the network `passphrase`
+pub(crate) fn envelope_hash(envelope: &TransactionEnvelope, passphrase: &str) -> Option<String> {
+    let tagged_transaction = match envelope {
+        TransactionEnvelope::Tx(e) => {
+            TransactionSignaturePayloadTaggedTransaction::Tx(e.tx.clone())
+        }
+        TransactionEnvelope::TxFeeBump(e) => {
+            TransactionSignaturePayloadTaggedTransaction::TxFeeBump(e.tx.clone())
+        }
+        TransactionEnvelope::TxV0(_) => return None,
+    };
+    let payload = TransactionSignaturePayload {
+        network_id: Hash(Sha256Hasher::hash(passphrase.as_bytes())),
+        tagged_transaction,
+    };
+    let bytes = payload.to_xdr(Limits::none()).ok()?;
+    Some(hex::encode(Sha256Hasher::hash(&bytes)))
+}
+
+// span fields must be declared up front, one per show::METRIC_KEYS entry
+fn metrics_span(hash: &str) -> Span {
+    info_span!(
+        "collect_transaction",
+        tx_hash = hash,
+        contract = Empty,
+        function = Empty,
+        cpu_insns = Empty,
+        mem_bytes = Empty,
+        entry_bytes = Empty,
+        entry_reads = Empty,
+        entry_writes = Empty,
+        read_bytes = Empty,
+        write_bytes = Empty,
+        min_txn_bytes = Empty,
+        args_bytes = Empty,
+        auth_bytes = Empty,
+        footprint_bytes = Empty,
+        signatures_bytes = Empty,
+        return_bytes = Empty,
+        wasm_bytes = Empty,
+        fee_charged = Empty,
+        resource_fee = Empty,
+        rent_fee = Empty,
+        events_count = Empty,
+        events_bytes = Empty,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::test::{transfer_tx, CONTRACT};
+    use crate::{MockOutcome, MockRpc, NetworkConfig, StellarRpcServer};
+    use soroban_client::keypair::{Keypair, KeypairBehavior};
+    use soroban_client::transaction::TransactionBehavior;
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    // enables every span so its fields can be inspected
+    struct Enabled;
+
+    impl Subscriber for Enabled {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, _: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, _: &Event<'_>) {}
+        fn enter(&self, _: &Id) {}
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn test_metrics_span_fields() {
+        tracing::subscriber::with_default(Enabled, || {
+            let span = metrics_span("hash");
+            for key in show::METRIC_KEYS {
+                assert!(span.field(key).is_some(), "{key} is not a span field");
+            }
+        });
+    }
+
+    async fn server(mock: &MockRpc) -> StellarRpcServer {
+        let config = NetworkConfig {
+            poll_interval: Duration::from_millis(10),
+            wait_time: Duration::from_millis(300),
+            ..Default::default()
+        };
+        StellarRpcServer::with_config(&mock.url(), MockRpc::options(), config)
+            .await
+            .unwrap()
+    }
+
+    async fn send(server: &mut StellarRpcServer) {
+        let keypair = Keypair::random().unwrap();
+        let mut tx = server
+            .prepare_transaction(&transfer_tx(&keypair))
+            .await
+            .unwrap();
+        tx.sign(&[keypair]);
+        server.send_transaction(tx).await.unwrap();
+    }
+
+    fn samples(server: &StellarRpcServer) -> usize {
+        let contract_id = stellar_strkey::Contract(CONTRACT).to_string();
+        server
+            .store()
+            .get(&contract_id)
+            .map_or(0, |funcs| funcs.values().map(Vec::len).sum())
+    }
+
+    #[test]
+    fn test_success() {
+        let mock = MockRpc::start().unwrap();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let mut server = server(&mock).await;
+            send(&mut server).await;
+            send(&mut server).await;
+            server.flush().await.unwrap();
+            assert_eq!(samples(&server), 2);
+        });
+    }
+
+    #[test]
+    fn test_failed() {
+        let mock = MockRpc::start().unwrap();
+        mock.set_outcome(MockOutcome::Failed);
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let mut server = server(&mock).await;
+            send(&mut server).await;
+            // found in a ledger, so resolved well before the wait time
+            let flush = tokio::time::timeout(Duration::from_millis(250), server.flush());
+            flush.await.unwrap().unwrap();
+            assert_eq!(samples(&server), 0);
+        });
+    }
+
+    #[test]
+    fn test_expired() {
+        let mock = MockRpc::start().unwrap();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let mut server = server(&mock).await;
+            send(&mut server).await;
+            server.flush().await.unwrap();
+            assert_eq!(samples(&server), 1);
+
+            mock.set_outcome(MockOutcome::Dropped);
+            send(&mut server).await;
+            let flush = tokio::time::timeout(Duration::from_secs(5), server.flush());
+            flush.await.unwrap().unwrap();
+            assert_eq!(samples(&server), 1);
+
+            // the rounds after the first one continue from its cursor
+            // instead of scanning the first transaction again
+            let params = mock.params("getTransactions");
+            assert!(params[0]["startLedger"].is_u64());
+            let last = params.last().unwrap();
+            assert!(last["startLedger"].is_null());
+            assert_eq!(last["pagination"]["cursor"], "1");
+        });
+    }
+}